@@ -42,10 +42,160 @@
 //! ```
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::iter;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const DEFAULT_COLUMN_SEPARATOR: &str = "  ";
+const ANSI_RESET: &str = "\x1b[0m";
+const ELISION_MARKER: &str = "...";
+
+/// Box-drawing characters used by borders.
+struct BoxChars {
+    horizontal: char,
+    vertical: char,
+    /// Left corner, junction, and right corner of the top border.
+    top: [char; 3],
+    /// Left tee, cross, and right tee of interior rules.
+    middle: [char; 3],
+    /// Left corner, junction, and right corner of the bottom border.
+    bottom: [char; 3],
+}
+
+/// Characters drawn by the table itself, as opposed to cell contents.
+struct Glyphs {
+    box_chars: BoxChars,
+    bordered_column_separator: &'static str,
+    legend_separator: &'static str,
+    truncation_marker: &'static str,
+    output_truncation_marker: &'static str,
+    skeleton_fill: char,
+    /// Filled and empty parts of bars.
+    bar: [char; 2],
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    box_chars: BoxChars {
+        horizontal: '─',
+        vertical: '│',
+        top: ['┌', '┬', '┐'],
+        middle: ['├', '┼', '┤'],
+        bottom: ['└', '┴', '┘'],
+    },
+    bordered_column_separator: " │ ",
+    legend_separator: " — ",
+    truncation_marker: "…",
+    output_truncation_marker: "… (output truncated)",
+    skeleton_fill: '·',
+    bar: ['█', '░'],
+};
+
+/// Fallbacks for terminals that cannot display UTF-8.
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    box_chars: BoxChars {
+        horizontal: '-',
+        vertical: '|',
+        top: ['+', '+', '+'],
+        middle: ['+', '+', '+'],
+        bottom: ['+', '+', '+'],
+    },
+    bordered_column_separator: " | ",
+    legend_separator: " - ",
+    truncation_marker: "...",
+    output_truncation_marker: "... (output truncated)",
+    skeleton_fill: '.',
+    bar: ['#', '.'],
+};
+
+/// Remove ANSI color sequences from a string.
+///
+/// This is the logic the table uses to ignore colors when sizing
+/// columns. Anything starting with `\x1b[`, up until the first final
+/// byte (`@` to `~`, e.g., `m`, `K`, or `H`), is considered a sequence.
+/// No allocation is made if the string does not contain any sequence.
+///
+/// # Examples
+///
+/// ```
+/// use verynicetable::strip_ansi;
+///
+/// assert_eq!(strip_ansi("\x1b[92m+ 137.89\x1b[0m"), "+ 137.89");
+/// assert_eq!(strip_ansi("\x1b[2K\x1b[1Gdone"), "done");
+/// assert_eq!(strip_ansi("no colors"), "no colors");
+/// ```
+#[must_use]
+pub fn strip_ansi(string: &str) -> Cow<'_, str> {
+    Table::strip_ansi_colors(string)
+}
+
+/// Number of visible characters in a string.
+///
+/// This is how the table measures cells: ANSI color sequences are
+/// ignored, and grapheme clusters (e.g., a letter followed by a
+/// combining accent) count as a single character.
+///
+/// # Examples
+///
+/// ```
+/// use verynicetable::visible_width;
+///
+/// assert_eq!(visible_width("\x1b[92m+ 137.89\x1b[0m"), 8);
+/// assert_eq!(visible_width("Cafe\u{301}"), 4);
+/// ```
+#[must_use]
+pub fn visible_width(string: &str) -> usize {
+    Table::visible_width(string)
+}
+
+/// Group the digits of a number by thousands.
+///
+/// Grouping goes from the right of the integer part, the fractional
+/// part is left untouched. Strings that aren't plain decimal numbers
+/// (an optional sign, digits, and an optional fractional part) are
+/// returned as is.
+///
+/// # Examples
+///
+/// ```
+/// use verynicetable::group_digits;
+///
+/// assert_eq!(group_digits("1234567", ','), "1,234,567");
+/// assert_eq!(group_digits("-1234.5678", ' '), "-1 234.5678");
+/// assert_eq!(group_digits("n/a", ','), "n/a");
+/// ```
+#[must_use]
+pub fn group_digits(number: &str, separator: char) -> Cow<'_, str> {
+    let unsigned = number.strip_prefix(['-', '+']).unwrap_or(number);
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let is_number = !integer.is_empty()
+        && integer.bytes().all(|byte| byte.is_ascii_digit())
+        && fraction.is_none_or(|fraction| fraction.bytes().all(|byte| byte.is_ascii_digit()));
+    if !is_number || integer.len() <= 3 {
+        return Cow::Borrowed(number);
+    }
+
+    let sign = &number[..number.len() - unsigned.len()];
+    let mut out = String::with_capacity(number.len() + integer.len() / 3);
+    out.push_str(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        out.push('.');
+        out.push_str(fraction);
+    }
+    Cow::Owned(out)
+}
 
 /// Ready-to-render `Table` blueprint with checks and conversions made.
 ///
@@ -58,11 +208,105 @@ const DEFAULT_COLUMN_SEPARATOR: &str = "  ";
 /// fields are ensured to be set, and it holds additional context for
 /// drawing (e.g., `columns_width`).
 struct TableBlueprint<'a> {
-    headers: Vec<&'a str>,
+    headers: Vec<Cow<'a, str>>,
     alignments: Vec<fmt::Alignment>,
-    data: Vec<Vec<&'a str>>,
+    rows: Vec<Row<'a>>,
     columns_width: Vec<usize>,
     column_separator: &'a str,
+    /// Separator of each gap between columns, if set per gap.
+    column_separators: Option<&'a [&'a str]>,
+    zebra: Option<(&'a str, &'a str)>,
+    margin_left: usize,
+    legend: &'a [(&'a str, &'a str)],
+    caption: Option<&'a str>,
+    /// Clock called at each render, if the timestamp caption is on.
+    now_fn: Option<fn() -> String>,
+    borders: bool,
+    /// Pad the last column even if left-aligned, for it to fill its width.
+    pad_last_column: bool,
+    flags: Flags,
+    glyphs: &'static Glyphs,
+    max_output_bytes: Option<usize>,
+    cell_padding: (usize, usize),
+    header_style: Option<(&'a str, &'a str)>,
+    cell_formatter: Option<&'a FormatCell<'a>>,
+    vertical_align: VerticalAlignment,
+    elision_alignment: Option<fmt::Alignment>,
+    line_ending: LineEnding,
+    /// Line rendered in place of the table, if it is too large.
+    summary: Option<String>,
+}
+
+/// Output of the renderer, exhausted once `max_bytes` is exceeded.
+struct Output<'o> {
+    inner: &'o mut dyn fmt::Write,
+    max_bytes: Option<usize>,
+    nb_bytes: usize,
+    is_exhausted: bool,
+    has_pending_newline: bool,
+}
+
+/// Cells of a data row, borrowed from the table until modified.
+///
+/// This is `Cow<[Cow<str>]>`, without making `Row` invariant.
+#[derive(Clone, Debug)]
+enum Cells<'a> {
+    Borrowed(&'a [Cow<'a, str>]),
+    Owned(Vec<Cow<'a, str>>),
+}
+
+impl<'a> Cells<'a> {
+    fn to_mut(&mut self) -> &mut Vec<Cow<'a, str>> {
+        if let Self::Borrowed(cells) = *self {
+            *self = Self::Owned(cells.to_vec());
+        }
+        match self {
+            Self::Borrowed(_) => unreachable!("cells were just cloned"),
+            Self::Owned(cells) => cells,
+        }
+    }
+
+    fn into_owned(self) -> Vec<Cow<'a, str>> {
+        match self {
+            Self::Borrowed(cells) => cells.to_vec(),
+            Self::Owned(cells) => cells,
+        }
+    }
+}
+
+impl<'a> std::ops::Deref for Cells<'a> {
+    type Target = [Cow<'a, str>];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(cells) => cells,
+            Self::Owned(cells) => cells,
+        }
+    }
+}
+
+impl PartialEq for Cells<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Cells<'_> {}
+
+/// Row of the table body, as seen by the renderer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Row<'a> {
+    /// Regular data row, split into cells, and its index among data
+    /// rows, after sorting.
+    Cells(Cells<'a>, usize),
+    /// Marker row standing in for the rows dropped by `max_rows`.
+    Elision,
+    /// Row spanning the entire width of the table.
+    Span(Cow<'a, str>, fmt::Alignment),
+    /// Horizontal rule with a title, splitting the table into sections.
+    Section(Cow<'a, str>),
+    /// Horizontal rule between groups of rows.
+    Rule,
 }
 
 /// `Table` builder.
@@ -93,16 +337,309 @@ struct TableBlueprint<'a> {
 ///
 /// Contrary to `Table`, `TableBuilder` can only hold valid
 /// ready-to-render state.
+///
+/// # Serde
+///
+/// With the `serde` feature, `Table` implements `Serialize` and
+/// `Deserialize`. Only the headers, alignments, `max_rows`, column
+/// separator, and data are saved. The rest of the settings are left
+/// out, and are at their defaults on deserialization.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Table<'a> {
-    headers: Option<Vec<&'a str>>,
-    alignments: Option<&'a [fmt::Alignment]>,
-    data: Option<Vec<Vec<&'a str>>>,
+    headers: Option<Vec<Cow<'a, str>>>,
+    alignments: Option<Cow<'a, [fmt::Alignment]>>,
+    data: Option<Vec<Vec<Cow<'a, str>>>>,
+    /// Span and section rows, and the data row they come before.
+    inserted_rows: Vec<(usize, Row<'a>)>,
     max_rows: Option<usize>,
-    column_separator: Option<&'a str>,
+    column_separator: Option<Cow<'a, str>>,
+    column_separators: Option<&'a [&'a str]>,
+    zebra: Option<(&'a str, &'a str)>,
+    margin_left: usize,
+    legend: Option<&'a [(&'a str, &'a str)]>,
+    header_case: HeaderCase,
+    borders: bool,
+    flags: Flags,
+    max_cell_width: Option<usize>,
+    column_overflow: Option<&'a [Overflow]>,
+    max_width: Option<usize>,
+    caption: Option<&'a str>,
+    now_fn: Option<Clock>,
+    columns: Option<&'a [usize]>,
+    summary_if_over: Option<(usize, &'a str)>,
+    sort_by_column: Option<(usize, SortOrder)>,
+    max_output_bytes: Option<usize>,
+    max_columns: Option<usize>,
+    decimal_align: Option<&'a [usize]>,
+    collision_marker: Option<&'a str>,
+    cell_padding: (usize, usize),
+    header_style: Option<(&'a str, &'a str)>,
+    /// Capacity of rows added with `push_row()`.
+    column_capacity: usize,
+    cell_formatter: Option<CellFormatter<'a>>,
+    group_numeric: Option<(&'a [usize], char)>,
+    pin_rows: Option<&'a [usize]>,
+    group_boundaries: Option<&'a [usize]>,
+    vertical_align: VerticalAlignment,
+    bar_columns: Vec<BarColumn>,
+    elision_alignment: Option<fmt::Alignment>,
+    fixed_column_widths: Option<&'a [Option<usize>]>,
+    row_numbers_header: &'a str,
+    align_on: Vec<(usize, char)>,
+    line_ending: LineEnding,
+    /// Invalidated by the setters that change the widths.
+    columns_width: WidthsCache,
+}
+
+/// Function returning the formatted current time.
+#[derive(Clone, Copy, Debug)]
+struct Clock(fn() -> String);
+
+impl PartialEq for Clock {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for Clock {}
+
+/// Function restyling a cell, given its row, column, and value.
+type FormatCell<'a> = dyn Fn(usize, usize, &str) -> Option<Cow<str>> + Send + Sync + 'a;
+
+/// Borrowed [`FormatCell`], compared by address.
+#[derive(Clone, Copy)]
+struct CellFormatter<'a>(&'a FormatCell<'a>);
+
+impl fmt::Debug for CellFormatter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CellFormatter(..)")
+    }
+}
+
+impl PartialEq for CellFormatter<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for CellFormatter<'_> {}
+
+/// On/off settings of a table, as a set of bits.
+///
+/// Each flag is named after the setter that toggles it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Flags(u16);
+
+impl Flags {
+    const TRIM_CELLS: Self = Self(1 << 0);
+    const SANITIZE_CELLS: Self = Self(1 << 1);
+    const PAD_SHORT_ROWS: Self = Self(1 << 2);
+    const TRUNCATE_LONG_ROWS: Self = Self(1 << 3);
+    const ESCAPE_SEPARATOR_COLLISIONS: Self = Self(1 << 4);
+    const TRIM_LAST_COLUMN: Self = Self(1 << 5);
+    const TRAILING_NEWLINE: Self = Self(1 << 6);
+    const ROW_NUMBERS: Self = Self(1 << 7);
+    const STRETCH_LAST_COLUMN: Self = Self(1 << 8);
+    const TRANSPOSE: Self = Self(1 << 9);
+    const REVERSE_COLUMNS: Self = Self(1 << 10);
+    const STRIP_COLORS: Self = Self(1 << 11);
+    const ASCII_SAFE: Self = Self(1 << 12);
+    const PAD_INSIDE_COLORS: Self = Self(1 << 13);
+    const TIMESTAMP_CAPTION: Self = Self(1 << 14);
+    const PRESERVE_CELL_WHITESPACE: Self = Self(1 << 15);
+
+    fn contains(self, flag: Self) -> bool {
+        self.0 & flag.0 == flag.0
+    }
+
+    fn set(&mut self, flag: Self, value: bool) {
+        if value {
+            self.0 |= flag.0;
+        } else {
+            self.0 &= !flag.0;
+        }
+    }
+}
+
+/// Widths of the parts of a column aligned on a character, around it.
+#[derive(Clone, Copy, Debug, Default)]
+struct SplitWidths {
+    prefix: usize,
+    suffix: Option<usize>,
+}
+
+/// Widths of the columns at the last render, until the table changes.
+///
+/// The cache is not part of the state of the table: tables compare
+/// equal whether it is filled or not.
+#[derive(Clone, Debug, Default)]
+struct WidthsCache(OnceLock<Vec<usize>>);
+
+impl WidthsCache {
+    fn invalidate(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for WidthsCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for WidthsCache {}
+
+/// Column rendered as bars, set with [`Table::bar_column()`].
+#[derive(Clone, Copy, Debug)]
+struct BarColumn {
+    column: usize,
+    max: f64,
+    width: usize,
+}
+
+impl PartialEq for BarColumn {
+    fn eq(&self, other: &Self) -> bool {
+        self.column == other.column
+            && self.max.to_bits() == other.max.to_bits()
+            && self.width == other.width
+    }
+}
+
+impl Eq for BarColumn {}
+
+/// What to do with cells wider than [`Table::max_cell_width()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overflow {
+    /// Truncate the cell, ending it with `…`.
+    #[default]
+    Truncate,
+    /// Leave the cell as is, ignoring the limit.
+    None,
+}
+
+/// Order of rows sorted with [`Table::sort_by_column()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SortOrder {
+    /// Smallest first.
+    #[default]
+    Asc,
+    /// Largest first.
+    Desc,
+}
+
+/// Vertical alignment of cells, in rows with multi-line cells.
+///
+/// See [`Table::vertical_align()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum VerticalAlignment {
+    /// First lines line up.
+    #[default]
+    Top,
+    /// Centered, one line higher if it cannot be exact.
+    Middle,
+    /// Last lines line up.
+    Bottom,
+}
+
+/// Line terminator of the rendered table.
+///
+/// See [`Table::line_ending()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\n`, as on Unix.
+    #[default]
+    Lf,
+    /// `\r\n`, as on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Case transformation applied to headers at render time.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HeaderCase {
+    /// Leave headers untouched.
+    #[default]
+    AsIs,
+    /// `UPPER CASE`.
+    Upper,
+    /// `lower case`.
+    Lower,
+    /// `Title Case`.
+    Title,
+}
+
+/// Reason a table cannot be rendered.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TableError {
+    /// Neither headers nor data were set.
+    Empty,
+    /// There are more alignments than headers.
+    AlignmentsMismatch,
+    /// A data row doesn't have as many cells as there are headers.
+    ColumnsMismatch,
+    /// The number of separators doesn't match the gaps between columns.
+    SeparatorsMismatch,
+    /// An index passed to [`Table::columns()`] has no matching column.
+    ColumnOutOfRange { index: usize, nb_columns: usize },
+    /// The alignment name at this index passed to
+    /// [`Table::alignments_from_str()`] is not recognized.
+    InvalidAlignment { index: usize },
+    /// The tables passed to [`Table::unified_diff()`] have different
+    /// numbers of columns.
+    DiffColumnsMismatch {
+        nb_columns: usize,
+        nb_other_columns: usize,
+    },
+    /// Rows were passed to [`Prepared::concat()`] for a transposed
+    /// table.
+    ConcatTransposed,
+    /// Writing to the output failed.
+    Write(fmt::Error),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "headers and data cannot both be empty"),
+            Self::AlignmentsMismatch => write!(f, "number of headers must match alignments"),
+            Self::ColumnsMismatch => write!(f, "number of headers must match columns in data"),
+            Self::SeparatorsMismatch => {
+                write!(f, "number of separators must match gaps between columns")
+            }
+            Self::ColumnOutOfRange { index, nb_columns } => {
+                write!(f, "column {index} out of range for {nb_columns} columns")
+            }
+            Self::InvalidAlignment { index } => write!(f, "alignment {index} is not recognized"),
+            Self::DiffColumnsMismatch {
+                nb_columns,
+                nb_other_columns,
+            } => write!(
+                f,
+                "cannot diff a table of {nb_columns} columns against one of {nb_other_columns}"
+            ),
+            Self::ConcatTransposed => write!(f, "cannot append rows to a transposed table"),
+            Self::Write(error) => write!(f, "cannot write table: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+impl From<fmt::Error> for TableError {
+    fn from(error: fmt::Error) -> Self {
+        Self::Write(error)
+    }
 }
 
-impl<'a> Default for Table<'a> {
+impl Default for Table<'_> {
     fn default() -> Self {
         Self::new()
     }
@@ -115,953 +652,7122 @@ impl<'a> Table<'a> {
             headers: None,
             alignments: None,
             data: None,
+            inserted_rows: Vec::new(),
             max_rows: None,
             column_separator: None,
+            column_separators: None,
+            zebra: None,
+            margin_left: 0,
+            legend: None,
+            header_case: HeaderCase::AsIs,
+            borders: false,
+            flags: Flags::TRAILING_NEWLINE,
+            max_cell_width: None,
+            column_overflow: None,
+            max_width: None,
+            caption: None,
+            now_fn: None,
+            columns: None,
+            summary_if_over: None,
+            sort_by_column: None,
+            max_output_bytes: None,
+            max_columns: None,
+            decimal_align: None,
+            collision_marker: None,
+            cell_padding: (0, 0),
+            header_style: None,
+            column_capacity: 0,
+            cell_formatter: None,
+            group_numeric: None,
+            pin_rows: None,
+            group_boundaries: None,
+            vertical_align: VerticalAlignment::Top,
+            bar_columns: Vec::new(),
+            elision_alignment: None,
+            fixed_column_widths: None,
+            row_numbers_header: "#",
+            align_on: Vec::new(),
+            line_ending: LineEnding::Lf,
+            columns_width: WidthsCache::default(),
+        }
+    }
+
+    /// Create a table with room for `rows` rows of `cols` cells, to be
+    /// added with [`push_row()`](Self::push_row).
+    ///
+    /// This saves reallocations when pushing many rows.
+    #[must_use]
+    pub fn with_capacity(rows: usize, cols: usize) -> Self {
+        Self {
+            data: Some(Vec::with_capacity(rows)),
+            column_capacity: cols,
+            ..Self::new()
         }
     }
 
     pub fn headers(&mut self, headers: &'a [impl AsRef<str>]) -> &mut Self {
-        let headers: Vec<&str> = headers.iter().map(AsRef::as_ref).collect();
+        self.columns_width.invalidate();
+        let headers: Vec<Cow<str>> = headers
+            .iter()
+            .map(|header| Cow::Borrowed(header.as_ref()))
+            .collect();
         self.headers = Some(headers);
         self
     }
 
+    /// Set the alignment of each column.
+    ///
+    /// The slice can be shorter than the number of columns, in which
+    /// case the remaining columns are left-aligned.
     pub fn alignments(&mut self, alignments: &'a [fmt::Alignment]) -> &mut Self {
-        self.alignments = Some(alignments);
+        self.columns_width.invalidate();
+        self.alignments = Some(Cow::Borrowed(alignments));
         self
     }
 
+    /// Set the alignment of each column by name.
+    ///
+    /// Names are case-insensitive: `l` or `left`, `r` or `right`, and
+    /// `c` or `center`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TableError::InvalidAlignment`] with the index of the
+    /// first unknown name. The alignments are left untouched then.
+    pub fn alignments_from_str(
+        &mut self,
+        alignments: &[impl AsRef<str>],
+    ) -> Result<&mut Self, TableError> {
+        let alignments = alignments
+            .iter()
+            .enumerate()
+            .map(
+                |(index, name)| match name.as_ref().to_lowercase().as_str() {
+                    "l" | "left" => Ok(fmt::Alignment::Left),
+                    "r" | "right" => Ok(fmt::Alignment::Right),
+                    "c" | "center" => Ok(fmt::Alignment::Center),
+                    _ => Err(TableError::InvalidAlignment { index }),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        self.columns_width.invalidate();
+        self.alignments = Some(Cow::Owned(alignments));
+        Ok(self)
+    }
+
+    /// Set the data, as a list of rows.
+    ///
+    /// Cells are rendered verbatim: leading and trailing whitespace is
+    /// kept, and counts towards the width of the column, unless
+    /// [`trim_cells()`](Self::trim_cells) is set.
     pub fn data(&mut self, data: &'a [Vec<impl AsRef<str>>]) -> &mut Self {
-        let data: Vec<Vec<&str>> = data
+        self.columns_width.invalidate();
+        let data: Vec<Vec<Cow<str>>> = data
             .iter()
-            .map(|row| row.iter().map(AsRef::as_ref).collect())
+            .map(|row| {
+                row.iter()
+                    .map(|cell| Cow::Borrowed(cell.as_ref()))
+                    .collect()
+            })
             .collect();
         self.data = Some(data);
         self
     }
 
-    pub fn max_rows(&mut self, max_rows: usize) -> &mut Self {
-        self.max_rows = Some(max_rows);
+    /// Append a row to the data.
+    ///
+    /// Cells can be borrowed (`&str`) or owned (`String`).
+    pub fn push_row(
+        &mut self,
+        row: impl IntoIterator<Item = impl Into<Cow<'a, str>>>,
+    ) -> &mut Self {
+        self.columns_width.invalidate();
+        let mut cells = Vec::with_capacity(self.column_capacity);
+        cells.extend(row.into_iter().map(Into::into));
+        self.data.get_or_insert_with(Vec::new).push(cells);
         self
     }
 
-    pub fn column_separator(&mut self, separator: &'a str) -> &mut Self {
-        self.column_separator = Some(separator);
+    /// Align cells vertically, in rows with multi-line cells.
+    ///
+    /// Data cells containing `\n` are rendered over as many lines, and
+    /// the other cells of the row are padded with blank lines. Columns
+    /// are as wide as their widest line.
+    pub fn vertical_align(&mut self, alignment: VerticalAlignment) -> &mut Self {
+        self.vertical_align = alignment;
         self
     }
 
-    #[allow(clippy::missing_errors_doc)]
-    pub fn render(&self, output: &mut fmt::Formatter) -> fmt::Result {
-        let table = self.make_table_blueprint();
+    /// Trim whitespace around the content of data cells.
+    ///
+    /// ANSI sequences are kept: in a cell that starts with sequences
+    /// and ends with a reset, the text in between is trimmed too. Cells
+    /// made of whitespace only become empty.
+    pub fn trim_cells(&mut self, trim: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::TRIM_CELLS, trim);
+        self
+    }
 
-        if table.data.is_empty() {
-            return writeln!(output, "{}", table.headers.join("  "));
-        }
+    /// Keep whitespace around the content of data cells, even if
+    /// [`trim_cells()`](Self::trim_cells) is set.
+    ///
+    /// The whitespace is rendered as is, and counts towards the width
+    /// of the column.
+    pub fn preserve_cell_whitespace(&mut self, preserve: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::PRESERVE_CELL_WHITESPACE, preserve);
+        self
+    }
+
+    /// Remove non-printing characters from headers and data cells.
+    ///
+    /// Control characters other than tabs and newlines, zero-width
+    /// characters, and BOMs take no room on screen but would count
+    /// toward the width of their column. ANSI sequences are kept.
+    pub fn sanitize_cells(&mut self, sanitize: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::SANITIZE_CELLS, sanitize);
+        self
+    }
+
+    /// Fill rows with fewer cells than columns with empty cells.
+    ///
+    /// Without headers, the number of columns is that of the longest
+    /// row. Otherwise, short rows make rendering fail.
+    pub fn pad_short_rows(&mut self, pad: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::PAD_SHORT_ROWS, pad);
+        self
+    }
 
-        let mut render_row = |row: &Vec<&str>| {
-            for (i, cell) in row.iter().enumerate() {
-                let width = table.columns_width[i];
-                let alignment = table.alignments[i];
+    /// Drop the extra cells of rows with more cells than columns.
+    ///
+    /// Otherwise, long rows make rendering fail.
+    pub fn truncate_long_rows(&mut self, truncate: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::TRUNCATE_LONG_ROWS, truncate);
+        self
+    }
 
-                let is_last_column = i == table.headers.len() - 1;
+    /// Append a row spanning the entire width of the table.
+    ///
+    /// The row is inserted after the data rows present at the time of
+    /// the call, and is not split into columns. It is aligned over the
+    /// total width of the table, separators included, and does not
+    /// influence the width of the columns.
+    ///
+    /// If the row ends up in the middle of rows dropped by
+    /// [`max_rows()`](Self::max_rows), it is dropped too.
+    pub fn push_span_row(&mut self, text: &'a str, alignment: fmt::Alignment) -> &mut Self {
+        self.columns_width.invalidate();
+        self.insert_row(Row::Span(Cow::Borrowed(text), alignment));
+        self
+    }
 
-                _ = match alignment {
-                    fmt::Alignment::Left if is_last_column => write!(output, "{cell}"),
-                    fmt::Alignment::Left => write!(output, "{}", Self::align_left(cell, width)),
-                    fmt::Alignment::Right => write!(output, "{}", Self::align_right(cell, width)),
-                    fmt::Alignment::Center => write!(output, "{}", Self::align_center(cell, width)),
-                };
+    /// Append a titled rule, splitting the table into sections.
+    ///
+    /// The section is inserted after the data rows present at the time
+    /// of the call. With [`borders()`](Self::borders), the title is
+    /// embedded in an interior border (`├─ Title ───┤`), otherwise it
+    /// is embedded in a banner (`── Title ─────`). Either way, the
+    /// rule spans the entire width of the table, and the title is
+    /// truncated if it doesn't fit.
+    ///
+    /// Like span rows, sections in the middle of rows dropped by
+    /// [`max_rows()`](Self::max_rows) are dropped too.
+    pub fn section(&mut self, title: &'a str) -> &mut Self {
+        self.columns_width.invalidate();
+        self.insert_row(Row::Section(Cow::Borrowed(title)));
+        self
+    }
+
+    /// Build a table from JSON, as an array of arrays or of objects.
+    ///
+    /// With arrays, the first one is used as headers if
+    /// `first_row_as_headers` is set. Short rows are padded with empty
+    /// cells. With objects, keys become headers in the order they are
+    /// first seen, and missing keys are filled with empty cells.
+    ///
+    /// Strings are used verbatim, `null` becomes an empty cell, and
+    /// other values are stringified as JSON (e.g., `42`, `true`). The
+    /// data is copied, so the table is not tied to `json`.
+    ///
+    /// Returns `None` if `json` is not a non-empty array of arrays, or
+    /// of objects.
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn from_json(
+        json: &serde_json::Value,
+        first_row_as_headers: bool,
+    ) -> Option<Table<'static>> {
+        use serde_json::Value;
+
+        let items = json.as_array().filter(|items| !items.is_empty())?;
+        let mut table = Table::new();
 
-                _ = if is_last_column {
-                    writeln!(output)
-                } else {
-                    write!(output, "{}", table.column_separator)
+        if items.iter().all(Value::is_object) {
+            let mut headers: Vec<&str> = Vec::new();
+            for key in items
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(|item| item.keys())
+            {
+                if !headers.contains(&key.as_str()) {
+                    headers.push(key);
                 }
             }
-        };
-
-        if !table.headers.iter().all(|header| header.is_empty()) {
-            render_row(&table.headers);
+            for item in items.iter().filter_map(Value::as_object) {
+                table.push_row(
+                    headers
+                        .iter()
+                        .map(|key| item.get(*key).map(Self::json_to_cell).unwrap_or_default()),
+                );
+            }
+            table.headers = Some(
+                headers
+                    .into_iter()
+                    .map(|key| Cow::Owned(key.to_string()))
+                    .collect(),
+            );
+        } else if items.iter().all(Value::is_array) {
+            let mut rows: Vec<Vec<String>> = items
+                .iter()
+                .filter_map(Value::as_array)
+                .map(|row| row.iter().map(Self::json_to_cell).collect())
+                .collect();
+            let nb_cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+            for row in &mut rows {
+                row.resize(nb_cols, String::new());
+            }
+            let mut rows = rows.into_iter();
+            if first_row_as_headers {
+                table.headers = rows
+                    .next()
+                    .map(|headers| headers.into_iter().map(Cow::Owned).collect());
+            }
+            for row in rows {
+                table.push_row(row);
+            }
+        } else {
+            return None;
         }
 
-        for row in table.data {
-            render_row(&row);
+        Some(table)
+    }
+
+    #[cfg(feature = "serde")]
+    fn json_to_cell(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::String(string) => string.clone(),
+            serde_json::Value::Null => String::new(),
+            value => value.to_string(),
         }
+    }
 
-        Ok(())
+    fn insert_row(&mut self, row: Row<'a>) {
+        let position = self.data.as_ref().map_or(0, Vec::len);
+        self.inserted_rows.push((position, row));
     }
 
-    /// Left-align string, ignoring ANSI color sequences.
+    /// Remove the data, keeping every other setting.
     ///
-    /// Without colors, it is equivalent to `{string:<width$}`.
-    fn align_left(string: &str, width: usize) -> Cow<str> {
-        let string_len_without_colors = Self::strip_ansi_colors(string).len();
-        let padding_len = width.saturating_sub(string_len_without_colors);
-        if padding_len == 0 {
-            return Cow::Borrowed(string);
-        }
-        Cow::Owned(format!("{string}{}", " ".repeat(padding_len)))
+    /// This lets a long-lived builder be reused across renders with
+    /// different data.
+    pub fn clear_data(&mut self) -> &mut Self {
+        self.columns_width.invalidate();
+        self.data = None;
+        self.inserted_rows.clear();
+        self
     }
 
-    /// Right-align string, ignoring ANSI color sequences.
+    /// Reset the builder to its initial state.
+    pub fn clear(&mut self) -> &mut Self {
+        *self = Self::new();
+        self
+    }
+
+    pub fn max_rows(&mut self, max_rows: usize) -> &mut Self {
+        self.columns_width.invalidate();
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Align the elision marker the same way in every column.
     ///
-    /// Without colors, it is equivalent to `{string:>width$}`.
-    fn align_right(string: &str, width: usize) -> Cow<str> {
-        let string_len_without_colors = Self::strip_ansi_colors(string).len();
-        let padding_len = width.saturating_sub(string_len_without_colors);
-        if padding_len == 0 {
-            return Cow::Borrowed(string);
-        }
-        Cow::Owned(format!("{}{string}", " ".repeat(padding_len)))
+    /// By default, the `...` of the row standing in for the rows dropped
+    /// by [`max_rows()`](Self::max_rows) follow the column alignments.
+    pub fn elision_alignment(&mut self, alignment: fmt::Alignment) -> &mut Self {
+        self.elision_alignment = Some(alignment);
+        self
     }
 
-    /// Center-align string, ignoring ANSI color sequences.
+    /// Keep the given data rows when eliding with [`max_rows()`](Self::max_rows).
     ///
-    /// Without colors, it is equivalent to `{string:^width$}`.
-    fn align_center(string: &str, width: usize) -> Cow<str> {
-        let string_len_without_colors = Self::strip_ansi_colors(string).len();
-        let padding_len = width.saturating_sub(string_len_without_colors);
-        if padding_len == 0 {
-            return Cow::Borrowed(string);
-        }
-        // `{string:^width$}` is left-biased if odd.
-        let padding_left = padding_len / 2;
-        let padding_right = padding_len - padding_left;
-        Cow::Owned(format!(
-            "{}{string}{}",
-            " ".repeat(padding_left),
-            " ".repeat(padding_right)
-        ))
+    /// Pinned rows count towards the limit, but are kept even if they
+    /// exceed it. They stay in place, with the elision marker wherever
+    /// rows were dropped. Like with
+    /// [`group_boundaries()`](Self::group_boundaries), indices refer to
+    /// data rows in the order they were added: after sorting, the row
+    /// is kept wherever it lands. Out-of-range indices are ignored.
+    pub fn pin_rows(&mut self, rows: &'a [usize]) -> &mut Self {
+        self.columns_width.invalidate();
+        self.pin_rows = Some(rows);
+        self
     }
 
-    /// Remove ANSI color sequences from strings.
+    /// Draw a rule before each of the given data rows, to split rows
+    /// into groups.
     ///
-    /// This function considers any sequence starting with `\x1b[`, up
-    /// until the first `m`, an ANSI sequence. It is naive, in the sense
-    /// that it won't bother to check whether se sequence is terminated,
-    /// or even valid. Basically, `\x1b[` starts stripping, and `m` ends
-    /// stripping. It's on the caller to only pass in valid sequences.
+    /// Indices refer to data rows in the order they were added: after
+    /// sorting, the rule is drawn before the row wherever it lands, but
+    /// never above the first row. Out-of-range indices are ignored.
+    /// Rules are kept by [`max_rows()`](Self::max_rows) if they sit next
+    /// to a kept row. They are not drawn if the table is transposed.
+    pub fn group_boundaries(&mut self, rows: &'a [usize]) -> &mut Self {
+        self.group_boundaries = Some(rows);
+        self
+    }
+
+    /// Align numbers on their decimal point, in the given columns.
     ///
-    /// This function delays allocation _until necessary_. As long as
-    /// the output matches the input (no ANSI sequence encountered), it
-    /// will not allocate memory. But, as soon as the output differs
-    /// from the input (ANSI sequence characters need to be removed),
-    /// memory for a new `String` will be allocated. This optimization
-    /// may seem far-fetched, but in the large majority of cases there
-    /// may be a lot of strings to process, but they most probably won't
-    /// be colored. So we make sure to save the overhead.
-    fn strip_ansi_colors(string: &str) -> Cow<str> {
-        enum State {
-            NotInSequence,
-            InSequence,
-        }
+    /// Numbers (an optional sign, digits possibly grouped with `,` as in
+    /// `1,234`, and an optional `.` and digits) are padded for their
+    /// decimal points to line up. Widths ignore ANSI sequences. Numbers
+    /// without a `.` align as if it were at the end. Other cells are
+    /// aligned within the column, following the column's alignment,
+    /// without disturbing the numbers.
+    ///
+    /// Indices refer to columns before selection with
+    /// [`columns()`](Self::columns). Out-of-range indices make
+    /// [`try_render()`](Self::try_render) fail.
+    pub fn decimal_align(&mut self, columns: &'a [usize]) -> &mut Self {
+        self.columns_width.invalidate();
+        self.decimal_align = Some(columns);
+        self
+    }
 
-        let mut state = State::NotInSequence;
-        let mut chars = string.chars().enumerate().peekable();
+    /// Align the cells of a column on their first `separator`.
+    ///
+    /// Cells are padded for their separators to line up, like numbers
+    /// with [`decimal_align()`](Self::decimal_align) (e.g., the `:` of
+    /// `host:port` values). Cells without `separator` align as if it
+    /// were at the end. This can be set for several columns.
+    ///
+    /// The index refers to columns before selection with
+    /// [`columns()`](Self::columns). An out-of-range index makes
+    /// [`try_render()`](Self::try_render) fail.
+    pub fn align_on(&mut self, column: usize, separator: char) -> &mut Self {
+        self.columns_width.invalidate();
+        self.align_on.retain(|(col, _)| *col != column);
+        self.align_on.push((column, separator));
+        self
+    }
+
+    /// Group the digits of numbers by thousands, in the given columns.
+    ///
+    /// Plain decimal numbers get `separator` inserted, as with
+    /// [`group_digits()`](crate::group_digits) (e.g., `1234567` becomes
+    /// `1,234,567`). Other cells are left untouched. Widths account for
+    /// the separators.
+    ///
+    /// Indices refer to columns before selection with
+    /// [`columns()`](Self::columns). Out-of-range indices make
+    /// [`try_render()`](Self::try_render) fail.
+    pub fn group_numeric(&mut self, columns: &'a [usize], separator: char) -> &mut Self {
+        self.columns_width.invalidate();
+        self.group_numeric = Some((columns, separator));
+        self
+    }
+
+    /// Render the numbers of a column as horizontal bars.
+    ///
+    /// Each cell becomes a bar of `width` characters, filled (`█`) in
+    /// proportion to its value over `max`, and empty (`░`) for the rest.
+    /// Values are clamped between `0` and `max`, and bars are empty if
+    /// `max` is not positive. Cells that are not finite numbers are
+    /// rendered blank. This can be set for several columns.
+    ///
+    /// The index refers to columns before selection with
+    /// [`columns()`](Self::columns). An out-of-range index makes
+    /// [`try_render()`](Self::try_render) fail.
+    pub fn bar_column(&mut self, column: usize, max: f64, width: usize) -> &mut Self {
+        self.columns_width.invalidate();
+        self.bar_columns.retain(|bar| bar.column != column);
+        self.bar_columns.push(BarColumn { column, max, width });
+        self
+    }
+
+    /// Limit the number of columns, like [`max_rows()`](Self::max_rows)
+    /// does for rows.
+    ///
+    /// If there are more columns, the first `max_columns - 1` are kept,
+    /// followed by a `…` column indicating there are more. With `0`,
+    /// only the `…` column is left.
+    pub fn max_columns(&mut self, max_columns: usize) -> &mut Self {
+        self.columns_width.invalidate();
+        self.max_columns = Some(max_columns);
+        self
+    }
+
+    pub fn column_separator(&mut self, separator: &'a str) -> &mut Self {
+        self.columns_width.invalidate();
+        self.column_separator = Some(Cow::Borrowed(separator));
+        self
+    }
+
+    /// Escape column separators appearing inside cells, for columns to
+    /// remain distinguishable.
+    ///
+    /// By default, separators inside cells are escaped with a backslash
+    /// (e.g., `"a  b"` becomes `"a\  b"` with the default separator).
+    /// Use [`collision_marker()`](Self::collision_marker) to replace
+    /// them with a marker instead. With [`borders()`](Self::borders),
+    /// the separator is `│`.
+    pub fn escape_separator_collisions(&mut self, escape: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::ESCAPE_SEPARATOR_COLLISIONS, escape);
+        self
+    }
+
+    /// Replace column separators appearing inside cells with `marker`,
+    /// instead of escaping them.
+    ///
+    /// This only applies with
+    /// [`escape_separator_collisions()`](Self::escape_separator_collisions).
+    pub fn collision_marker(&mut self, marker: &'a str) -> &mut Self {
+        self.columns_width.invalidate();
+        self.collision_marker = Some(marker);
+        self
+    }
+
+    /// Set a different separator for each gap between columns.
+    ///
+    /// There must be one separator per gap, i.e., one less than the
+    /// number of columns, or [`try_render()`](Self::try_render) fails.
+    /// This takes precedence over
+    /// [`column_separator()`](Self::column_separator), but not over
+    /// [`borders()`](Self::borders).
+    pub fn column_separators(&mut self, separators: &'a [&'a str]) -> &mut Self {
+        self.columns_width.invalidate();
+        self.column_separators = Some(separators);
+        self
+    }
+
+    /// Like [`column_separator()`](Self::column_separator), but for a
+    /// separator computed at runtime.
+    pub fn column_separator_owned(&mut self, separator: String) -> &mut Self {
+        self.columns_width.invalidate();
+        self.column_separator = Some(Cow::Owned(separator));
+        self
+    }
+
+    /// Stripe data rows with alternating styles.
+    ///
+    /// `even_style` applies to the first data row, `odd_style` to the
+    /// second, and so on. Styles are meant to be ANSI sequences (e.g.,
+    /// `"\x1b[2m"`), and each styled row is terminated by a reset.
+    /// Headers are left unstyled.
+    pub fn zebra(&mut self, even_style: &'a str, odd_style: &'a str) -> &mut Self {
+        self.zebra = Some((even_style, odd_style));
+        self
+    }
+
+    /// Never emit trailing spaces in the last column, whatever its
+    /// alignment.
+    ///
+    /// Left-aligned last columns are never padded on the right. With
+    /// this, center-aligned last columns aren't either, which makes for
+    /// diff-clean output. Right-aligned last columns are unaffected,
+    /// except for the right [`cell_padding()`](Self::cell_padding).
+    /// This has no effect with [`borders()`](Self::borders).
+    pub fn trim_last_column(&mut self, trim: bool) -> &mut Self {
+        self.flags.set(Flags::TRIM_LAST_COLUMN, trim);
+        self
+    }
+
+    /// Terminate lines with `\n` (the default) or `\r\n`.
+    ///
+    /// This applies to every line, including the last one unless
+    /// [`trailing_newline()`](Self::trailing_newline) is off.
+    pub fn line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// End the output with a newline (the default), or not.
+    ///
+    /// Disable it when composing the table into a larger output that
+    /// adds its own newline.
+    pub fn trailing_newline(&mut self, trailing_newline: bool) -> &mut Self {
+        self.flags.set(Flags::TRAILING_NEWLINE, trailing_newline);
+        self
+    }
+
+    /// Indent every line of the table by `margin` spaces.
+    pub fn margin_left(&mut self, margin: usize) -> &mut Self {
+        self.margin_left = margin;
+        self
+    }
+
+    /// Draw a box around the table, and a rule below the headers.
+    ///
+    /// Columns are separated by `│` instead of the column separator.
+    pub fn borders(&mut self, borders: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.borders = borders;
+        self
+    }
+
+    /// Add spaces on each side of every cell, header included.
+    ///
+    /// This widens every column uniformly, independently of the column
+    /// separator (and of the space inside borders). Like regular
+    /// padding, right padding is not added to a left-aligned last
+    /// column.
+    pub fn cell_padding(&mut self, left: usize, right: usize) -> &mut Self {
+        self.cell_padding = (left, right);
+        self
+    }
+
+    /// Limit the width of data cells.
+    ///
+    /// Wider cells are truncated and end with `…`, unless their column
+    /// opts out with [`column_overflow()`](Self::column_overflow).
+    pub fn max_cell_width(&mut self, width: usize) -> &mut Self {
+        self.columns_width.invalidate();
+        self.max_cell_width = Some(width);
+        self
+    }
+
+    /// Set how each column handles cells exceeding `max_cell_width`.
+    ///
+    /// Per-column settings take precedence over the global limit: a
+    /// column set to [`Overflow::None`] renders its cells in full, even
+    /// if [`max_cell_width()`](Self::max_cell_width) is set. Columns
+    /// past the end of the slice default to [`Overflow::Truncate`].
+    pub fn column_overflow(&mut self, overflows: &'a [Overflow]) -> &mut Self {
+        self.columns_width.invalidate();
+        self.column_overflow = Some(overflows);
+        self
+    }
+
+    /// Limit the width of the table, margin included.
+    ///
+    /// If the table is wider, the widest columns are narrowed first,
+    /// one character at a time, and their cells are truncated with `…`.
+    /// Columns are never narrowed below one character, so the table can
+    /// still exceed `max_width` if it has too many columns.
+    pub fn max_width(&mut self, width: usize) -> &mut Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Prepend a column numbering data rows, starting at 1.
+    ///
+    /// Rows are numbered after sorting, and keep their number when rows
+    /// before them are elided by [`max_rows()`](Self::max_rows). The
+    /// column is right-aligned, and counts as a column for the settings
+    /// that apply after selection with [`columns()`](Self::columns)
+    /// (e.g., `column_separators()`).
+    pub fn row_numbers(&mut self, row_numbers: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::ROW_NUMBERS, row_numbers);
+        self
+    }
+
+    /// Header of the [`row_numbers()`](Self::row_numbers) column.
+    ///
+    /// Defaults to `#`. It is left blank if all other headers are.
+    pub fn row_numbers_header(&mut self, header: &'a str) -> &mut Self {
+        self.columns_width.invalidate();
+        self.row_numbers_header = header;
+        self
+    }
+
+    /// Force the width of columns, whatever their content.
+    ///
+    /// Columns set to `Some(width)` are exactly `width` wide: shorter
+    /// cells are padded, and longer cells (headers included) are
+    /// truncated with `…`. Columns set to `None`, or past the end of the
+    /// slice, are sized to fit their content. This helps aligning tables
+    /// rendered separately. [`max_width()`](Self::max_width) can still
+    /// narrow fixed columns.
+    ///
+    /// Indices refer to columns before selection with
+    /// [`columns()`](Self::columns).
+    pub fn fixed_column_widths(&mut self, widths: &'a [Option<usize>]) -> &mut Self {
+        self.fixed_column_widths = Some(widths);
+        self
+    }
+
+    /// Give the slack left by [`max_width()`](Self::max_width) to the
+    /// last column.
+    ///
+    /// The last column is widened for the table to fill exactly
+    /// `max_width`. If the table is already as wide, or wider, this is
+    /// a no-op.
+    pub fn stretch_last_column(&mut self, stretch: bool) -> &mut Self {
+        self.flags.set(Flags::STRETCH_LAST_COLUMN, stretch);
+        self
+    }
+
+    /// Restyle data cells, at render time.
+    ///
+    /// The formatter is called with the row and column of each data
+    /// cell, as rendered (i.e., after sorting and column selection), and
+    /// its value. If it returns `Some`, the returned string is rendered
+    /// instead of the value. Rows dropped by
+    /// [`max_rows()`](Self::max_rows) still count, so the row matches
+    /// the one of [`row_numbers()`](Self::row_numbers), minus one.
+    ///
+    /// The formatter is borrowed, like the data, so it can borrow too
+    /// (e.g., a threshold, or a lookup table).
+    ///
+    /// Widths are computed from the original values, so the formatter
+    /// must preserve the visible width. It is meant to wrap cells in
+    /// ANSI sequences, which do not count towards the width.
+    ///
+    /// ```rust
+    /// use std::borrow::Cow;
+    /// use verynicetable::Table;
+    ///
+    /// let table = Table::new()
+    ///     .headers(&["X"])
+    ///     .data(&[vec!["-7"], vec!["3"]])
+    ///     .cell_formatter(&|_, _, value| {
+    ///         value
+    ///             .starts_with('-')
+    ///             .then(|| Cow::Owned(format!("\x1b[31m{value}\x1b[0m")))
+    ///     })
+    ///     .to_string();
+    ///
+    /// assert_eq!(table, "X\n\x1b[31m-7\x1b[0m\n3\n");
+    /// ```
+    pub fn cell_formatter(
+        &mut self,
+        formatter: &'a (impl Fn(usize, usize, &str) -> Option<Cow<str>> + Send + Sync),
+    ) -> &mut Self {
+        self.cell_formatter = Some(CellFormatter(formatter));
+        self
+    }
+
+    /// Wrap each header cell in a style, at render time.
+    ///
+    /// `prefix` and `suffix` are meant to be ANSI sequences (e.g.,
+    /// `"\x1b[1m"` and `"\x1b[0m"`). The suffix comes after the padding,
+    /// so the whole cell is styled. Widths are unaffected.
+    pub fn header_style(&mut self, prefix: &'a str, suffix: &'a str) -> &mut Self {
+        self.header_style = Some((prefix, suffix));
+        self
+    }
+
+    /// Change the case of headers at render time.
+    ///
+    /// The columns are sized according to the transformed headers. Data
+    /// is left untouched, and so are ANSI sequences in headers.
+    pub fn header_transform(&mut self, case: HeaderCase) -> &mut Self {
+        self.columns_width.invalidate();
+        self.header_case = case;
+        self
+    }
+
+    /// Explain symbols used in the table, in a block below it.
+    ///
+    /// Each entry is a `(symbol, description)` pair, rendered as
+    /// `symbol — description`. Descriptions are wrapped to the width of
+    /// the table.
+    pub fn legend(&mut self, legend: &'a [(&'a str, &'a str)]) -> &mut Self {
+        self.legend = Some(legend);
+        self
+    }
+
+    /// Sort data rows by the values in a column, at render time.
+    ///
+    /// Values are compared without their ANSI sequences. If two values
+    /// are numbers, they are compared numerically (`"9"` before `"10"`),
+    /// otherwise they are compared lexicographically, with numbers
+    /// before text. The sort is stable: ties keep their input order.
+    ///
+    /// Sorting happens before [`max_rows()`](Self::max_rows), so the
+    /// rows kept are the sorted extremes. Span and section rows stay in
+    /// place. `column` is the index of the column before selection with
+    /// [`columns()`](Self::columns).
+    pub fn sort_by_column(&mut self, column: usize, order: SortOrder) -> &mut Self {
+        self.columns_width.invalidate();
+        self.sort_by_column = Some((column, order));
+        self
+    }
+
+    /// Select and order columns by index, without touching the data.
+    ///
+    /// Headers, alignments, and data are projected through `columns`.
+    /// An index can be repeated to duplicate a column. Per-column
+    /// settings still refer to the original columns. Out-of-range
+    /// indices make [`try_render()`](Self::try_render) fail.
+    pub fn columns(&mut self, columns: &'a [usize]) -> &mut Self {
+        self.columns_width.invalidate();
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Render a single summary line instead of the table, if there are
+    /// more than `rows` data rows.
+    ///
+    /// This protects terminals from giant dumps. In `template`, `{rows}`
+    /// is replaced by the number of data rows (with digits grouped, e.g.,
+    /// `1,234`) and `{cols}` by the number of columns (after selection
+    /// with [`columns()`](Self::columns), row numbers aside). For
+    /// example: `"{rows} rows × {cols} columns (too large to display)"`.
+    pub fn summary_if_over(&mut self, rows: usize, template: &'a str) -> &mut Self {
+        self.columns_width.invalidate();
+        self.summary_if_over = Some((rows, template));
+        self
+    }
+
+    /// Stop rendering once the output exceeds `max_bytes` bytes.
+    ///
+    /// The line crossing the limit is written in full, followed by a
+    /// `… (output truncated)` line, and the rest of the table is
+    /// dropped. This protects logs from runaway output.
+    pub fn max_output_bytes(&mut self, max_bytes: usize) -> &mut Self {
+        self.max_output_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Render headers as the first column, and each row as a column.
+    ///
+    /// This suits key-value displays of a single record. Labels and
+    /// values are left-aligned, and there is no header line. Span and
+    /// section rows are left out. This applies after selection with
+    /// [`columns()`](Self::columns), so `max_columns()` and
+    /// `column_separators()` count the transposed columns.
+    pub fn transpose(&mut self, transpose: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::TRANSPOSE, transpose);
+        self
+    }
+
+    /// Render columns in reverse order, without touching the data.
+    ///
+    /// This applies after selection with [`columns()`](Self::columns).
+    pub fn reverse_columns(&mut self, reverse: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::REVERSE_COLUMNS, reverse);
+        self
+    }
+
+    /// Remove ANSI color sequences from the output.
+    ///
+    /// This is useful when the output is not a terminal (e.g., piped
+    /// to a file). It covers everything: headers, cells, zebra styles,
+    /// legend, etc. Alignment is unaffected, since the table ignores
+    /// colors when measuring anyway.
+    ///
+    /// To honor the [`NO_COLOR`](https://no-color.org) convention:
+    ///
+    /// ```
+    /// # use verynicetable::Table;
+    /// let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    /// let table = Table::new()
+    ///     .headers(&["\x1b[1mNAME\x1b[0m"])
+    ///     .strip_colors(no_color)
+    ///     .to_string();
+    /// ```
+    pub fn strip_colors(&mut self, strip: bool) -> &mut Self {
+        self.flags.set(Flags::STRIP_COLORS, strip);
+        self
+    }
+
+    /// Draw borders, markers, and bars with ASCII characters only.
+    ///
+    /// This is for terminals that cannot display UTF-8: `…` becomes
+    /// `...`, and box-drawing characters become `+`, `-`, and `|`.
+    /// Cell contents are left as is.
+    pub fn ascii_safe(&mut self, ascii_safe: bool) -> &mut Self {
+        self.columns_width.invalidate();
+        self.flags.set(Flags::ASCII_SAFE, ascii_safe);
+        self
+    }
+
+    /// Insert padding inside the colors of cells, for backgrounds to
+    /// fill the entire width of the column.
+    ///
+    /// This applies to cells starting with an ANSI sequence and ending
+    /// with a reset (`\x1b[0m`), e.g., `"\x1b[41mfoo\x1b[0m"`. Such
+    /// cells get padded even if they are in the last column. Other cells
+    /// are padded as usual, outside of any coloring.
+    pub fn pad_inside_colors(&mut self, pad_inside_colors: bool) -> &mut Self {
+        self.flags.set(Flags::PAD_INSIDE_COLORS, pad_inside_colors);
+        self
+    }
+
+    /// Add a caption line below the table.
+    pub fn caption(&mut self, caption: &'a str) -> &mut Self {
+        self.caption = Some(caption);
+        self
+    }
+
+    /// Add a caption line with the time of rendering.
+    ///
+    /// This makes stale output obvious, in dashboards or snapshots. The
+    /// line comes after the [`caption()`](Self::caption), if any. Time
+    /// is formatted as `YYYY-MM-DD HH:MM:SS UTC`, unless a custom clock
+    /// is set with [`now_fn()`](Self::now_fn).
+    pub fn timestamp_caption(&mut self, timestamp: bool) -> &mut Self {
+        self.flags.set(Flags::TIMESTAMP_CAPTION, timestamp);
+        self
+    }
+
+    /// Set the clock used by [`timestamp_caption()`], returning the
+    /// formatted current time.
+    ///
+    /// Use this to format the time differently (e.g., in local time),
+    /// or to get reproducible output.
+    ///
+    /// [`timestamp_caption()`]: Self::timestamp_caption
+    pub fn now_fn(&mut self, now_fn: fn() -> String) -> &mut Self {
+        self.now_fn = Some(Clock(now_fn));
+        self
+    }
+
+    /// Current time, as `YYYY-MM-DD HH:MM:SS UTC`.
+    fn now_utc() -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::format_utc_timestamp(timestamp)
+    }
+
+    /// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
+    ///
+    /// Days are converted to a civil date following Howard Hinnant's
+    /// `civil_from_days` algorithm, with eras of 400 years.
+    fn format_utc_timestamp(timestamp: u64) -> String {
+        let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+        let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+        // Shift the epoch to 0000-03-01, so leap days end the year.
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153; // March is 0.
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        format!("{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02} UTC")
+    }
+
+    /// Width and height of the rendered table, in visible characters.
+    ///
+    /// ANSI sequences do not count towards the width. The width is
+    /// that of the longest line, margin included.
+    #[must_use]
+    pub fn dimensions(&self) -> (usize, usize) {
+        let table = self.to_string();
+        let width = table.lines().map(Self::visible_width).max().unwrap_or(0);
+        let height = table.lines().count();
+        (width, height)
+    }
+
+    /// Width of each rendered column, in visible characters.
+    ///
+    /// This is the width of the content, after elision, selection, and
+    /// width limits, without separators or [`cell_padding()`](Self::cell_padding).
+    /// Nothing is rendered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]).
+    #[must_use]
+    pub fn column_widths(&self) -> Vec<usize> {
+        self.make_table_blueprint().columns_width
+    }
+
+    /// Render the table to a `fmt::Formatter`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to `output` fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]). Use
+    /// [`try_render()`](Self::try_render) to handle it instead.
+    pub fn render(&self, output: &mut fmt::Formatter) -> fmt::Result {
+        self.make_table_blueprint().render(output)
+    }
+
+    /// Render the table to any `fmt::Write`, without panicking.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the table is invalid, or if writing to `output` fails.
+    pub fn try_render(&self, output: &mut impl fmt::Write) -> Result<(), TableError> {
+        self.try_make_table_blueprint()?.render(output)?;
+        Ok(())
+    }
+
+    /// Append the rendered table to `buf`.
+    ///
+    /// Room is reserved ahead for the whole table, so building a report
+    /// out of many tables does not need intermediate strings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]). Use
+    /// [`try_render()`](Self::try_render) to handle it instead.
+    pub fn render_into(&self, buf: &mut String) {
+        let table = self.make_table_blueprint();
+        buf.reserve(table.estimated_len());
+        table
+            .render(buf)
+            .expect("writing to a `String` cannot fail");
+    }
+
+    /// Run checks and conversions once, for a table to be rendered
+    /// many times, or to be extended with [`Prepared::concat()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]).
+    #[must_use]
+    pub fn prepare(&self) -> Prepared<'_> {
+        self.try_prepare().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Row-level diff against `other`, rendered as a table.
+    ///
+    /// Rows are matched by their value in `key_column`, and prefixed
+    /// with a marker column: `-` for rows only in `self`, `+` for rows
+    /// only in `other`, and ` ` for rows in both. Rows with the same key
+    /// but different values are shown removed, then added. If several
+    /// rows share a key, they are matched in order. Rows of `self` come
+    /// first, followed by the rows added in `other`.
+    ///
+    /// Headers, alignments, and layout (column separator, borders, and
+    /// margin) come from `self`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if either table is invalid (see [`TableError`]), if their
+    /// number of columns differ ([`TableError::DiffColumnsMismatch`]),
+    /// or if `key_column` is out of range.
+    pub fn unified_diff(&self, other: &Table, key_column: usize) -> Result<String, TableError> {
+        self.try_make_table_blueprint()?;
+        other.try_make_table_blueprint()?;
+
+        let nb_cols = self.determine_nb_columns()?;
+        let nb_other_cols = other.determine_nb_columns()?;
+        if nb_cols != nb_other_cols {
+            return Err(TableError::DiffColumnsMismatch {
+                nb_columns: nb_cols,
+                nb_other_columns: nb_other_cols,
+            });
+        }
+        if key_column >= nb_cols {
+            return Err(TableError::ColumnOutOfRange {
+                index: key_column,
+                nb_columns: nb_cols,
+            });
+        }
+
+        // Rows are compared as rendered: padded, truncated, trimmed, and
+        // sanitized following the settings of their own table.
+        let mut old_rows = self.get_data_or_default();
+        self.clean_rows(&mut old_rows, nb_cols);
+        let mut new_rows = other.get_data_or_default();
+        other.clean_rows(&mut new_rows, nb_other_cols);
+
+        // Rows sharing a key are matched in order.
+        let mut new_by_key: HashMap<&str, VecDeque<usize>> = HashMap::new();
+        for (i, row) in new_rows.iter().enumerate() {
+            if let Some(key) = row.get(key_column) {
+                new_by_key.entry(key.as_ref()).or_default().push_back(i);
+            }
+        }
+        let mut is_matched = vec![false; new_rows.len()];
+
+        let mut table = Table::new();
+        let marked = |marker: &'static str, row: &[Cow<'_, str>]| -> Vec<String> {
+            iter::once(marker.to_string())
+                .chain(row.iter().map(ToString::to_string))
+                .collect()
+        };
+        for row in &old_rows {
+            let matching = row
+                .get(key_column)
+                .and_then(|key| new_by_key.get_mut(key.as_ref()))
+                .and_then(VecDeque::pop_front);
+            match matching {
+                Some(i) => {
+                    is_matched[i] = true;
+                    if *new_rows[i] == **row {
+                        table.push_row(marked(" ", row));
+                    } else {
+                        table
+                            .push_row(marked("-", row))
+                            .push_row(marked("+", &new_rows[i]));
+                    }
+                }
+                None => {
+                    table.push_row(marked("-", row));
+                }
+            }
+        }
+        for (row, is_matched) in new_rows.iter().zip(is_matched) {
+            if !is_matched {
+                table.push_row(marked("+", row));
+            }
+        }
+
+        let headers: Vec<Cow<str>> = iter::once(Cow::Borrowed(""))
+            .chain(self.get_headers_or_default(nb_cols))
+            .collect();
+        let alignments: Vec<fmt::Alignment> = iter::once(fmt::Alignment::Left)
+            .chain(self.get_alignments_or_default(nb_cols))
+            .collect();
+        table
+            .headers(&headers)
+            .alignments(&alignments)
+            .borders(self.borders)
+            .margin_left(self.margin_left);
+        if let Some(separator) = self.column_separator.as_deref() {
+            table.column_separator(separator);
+        }
+
+        let mut diff = String::new();
+        table.try_render(&mut diff)?;
+        Ok(diff)
+    }
+
+    /// Render the table in a canonical form, for snapshot testing.
+    ///
+    /// The canonical form only depends on the content: columns are
+    /// separated by a single space, ANSI sequences are stripped, and
+    /// trailing whitespace is trimmed. Settings that only affect the
+    /// style or width (borders, margin, zebra, header case, width
+    /// limits, etc.) are ignored, and so is the timestamp caption.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]).
+    #[must_use]
+    pub fn canonical(&self) -> String {
+        let mut table = self.clone();
+        table.column_separator = Some(Cow::Borrowed(" "));
+        table.column_separators = None;
+        table.borders = false;
+        table.zebra = None;
+        table.margin_left = 0;
+        table.header_case = HeaderCase::AsIs;
+        table.max_cell_width = None;
+        table.max_width = None;
+        table.fixed_column_widths = None;
+        table.flags.set(Flags::STRETCH_LAST_COLUMN, false);
+        table.flags.set(Flags::PAD_INSIDE_COLORS, false);
+        table.cell_padding = (0, 0);
+        table.flags.set(Flags::TIMESTAMP_CAPTION, false);
+        table.flags.set(Flags::STRIP_COLORS, true);
+        table.cell_formatter = None;
+        table.columns_width.invalidate();
+
+        let rendered = table.to_string();
+        let mut out = String::with_capacity(rendered.len());
+        for line in rendered.lines() {
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render the structure of the table, with all content blanked.
+    ///
+    /// Every cell, span row, and section title is replaced by `·` over
+    /// its width, leaving borders, rules, and separators as they are.
+    /// This helps debugging alignment issues. The caption and the legend
+    /// are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is invalid (see [`TableError`]).
+    #[must_use]
+    pub fn render_grid_skeleton(&self) -> String {
+        let mut table = self.make_table_blueprint();
+        let fill = table.glyphs.skeleton_fill;
+        let blank = |width: usize| Cow::Owned(fill.to_string().repeat(width));
+        // Multi-line cells keep their number of lines.
+        let columns_width = table.columns_width.clone();
+        let blank_cells = |cells: &[Cow<str>]| -> Vec<Cow<str>> {
+            let height = cells.iter().map(|cell| cell.split('\n').count()).max();
+            columns_width
+                .iter()
+                .map(|width| Cow::Owned(vec![blank(*width); height.unwrap_or(1)].join("\n")))
+                .collect()
+        };
+
+        if !table.headers.iter().all(|header| header.is_empty()) {
+            table.headers = blank_cells(&table.headers);
+        }
+        for row in &mut table.rows {
+            *row = match row {
+                Row::Cells(cells, index) => Row::Cells(Cells::Owned(blank_cells(cells)), *index),
+                Row::Elision => Row::Cells(Cells::Owned(blank_cells(&[])), 0),
+                Row::Span(text, alignment) => {
+                    Row::Span(blank(Self::visible_width(text)), *alignment)
+                }
+                Row::Section(title) => Row::Section(blank(Self::visible_width(title))),
+                Row::Rule => Row::Rule,
+            };
+        }
+        table.zebra = None;
+        table.caption = None;
+        table.now_fn = None;
+        table.cell_formatter = None;
+        table.legend = &[];
+        table.summary = None;
+
+        let mut out = String::with_capacity(table.estimated_len());
+        table
+            .render(&mut out)
+            .expect("writing to a `String` cannot fail");
+        out
+    }
+
+    /// Render the table as a `DokuWiki` table.
+    ///
+    /// Header cells are delimited by `^`, data cells by `|`. Alignment
+    /// follows `DokuWiki`'s convention of padding cells with extra
+    /// spaces: on the left to align right, on both sides to center.
+    /// Span rows span all columns, sections become header cells
+    /// spanning all columns, and ANSI sequences are stripped. Line
+    /// breaks in cells become forced line breaks (`\\`), and `|` or `^`
+    /// in cells are escaped as nowiki text (`%%...%%`).
+    #[must_use]
+    pub fn to_dokuwiki(&self) -> String {
+        let table = self.make_table_blueprint();
+        let nb_cols = table.headers.len();
+        let mut out = String::new();
+
+        if !table.headers.iter().all(|header| header.is_empty()) {
+            out.push('^');
+            for header in &table.headers {
+                out.push_str(&Self::dokuwiki_cell(header, fmt::Alignment::Left));
+                out.push('^');
+            }
+            out.push('\n');
+        }
+
+        let elision_row = vec![Cow::Borrowed(ELISION_MARKER); nb_cols];
+
+        for row in &table.rows {
+            let cells: &[Cow<str>] = match row {
+                Row::Cells(cells, _) => cells,
+                Row::Elision => &elision_row,
+                Row::Span(text, alignment) => {
+                    out.push_str(&Self::dokuwiki_span(text, *alignment, '|', nb_cols));
+                    continue;
+                }
+                Row::Section(title) => {
+                    out.push_str(&Self::dokuwiki_span(
+                        title,
+                        fmt::Alignment::Left,
+                        '^',
+                        nb_cols,
+                    ));
+                    continue;
+                }
+                // DokuWiki tables have no rules.
+                Row::Rule => continue,
+            };
+            out.push('|');
+            for (cell, alignment) in cells.iter().zip(&table.alignments) {
+                out.push_str(&Self::dokuwiki_cell(cell, *alignment));
+                out.push('|');
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Cell spanning all columns, as empty cells merge into the previous.
+    fn dokuwiki_span(
+        text: &str,
+        alignment: fmt::Alignment,
+        delimiter: char,
+        nb_cols: usize,
+    ) -> String {
+        let cell = Self::dokuwiki_cell(text, alignment);
+        let merged = delimiter.to_string().repeat(nb_cols.saturating_sub(1));
+        format!("{delimiter}{cell}{delimiter}{merged}\n")
+    }
+
+    fn dokuwiki_cell(cell: &str, alignment: fmt::Alignment) -> String {
+        let mut cell = Self::strip_ansi_colors(cell);
+        if cell.contains(['\n', '|', '^']) {
+            // Delimiters are escaped as nowiki text (`%%...%%`), line by
+            // line for line breaks to remain.
+            let lines: Vec<Cow<str>> = cell
+                .split('\n')
+                .map(|line| {
+                    if line.contains(['|', '^']) {
+                        Cow::Owned(format!("%%{line}%%"))
+                    } else {
+                        Cow::Borrowed(line)
+                    }
+                })
+                .collect();
+            cell = Cow::Owned(lines.join("\\\\ "));
+        }
+        match alignment {
+            fmt::Alignment::Left => format!(" {cell} "),
+            fmt::Alignment::Right => format!("  {cell} "),
+            fmt::Alignment::Center => format!("  {cell}  "),
+        }
+    }
+
+    /// Split text into lines of at most `width` visible characters.
+    ///
+    /// Lines are broken at whitespace. Words longer than `width` are
+    /// not broken, and get a line of their own.
+    fn wrap_words(text: &str, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in text.split_whitespace() {
+            let word_width = Self::visible_width(word);
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+
+        lines
+    }
+
+    /// Left-align string, ignoring ANSI color sequences.
+    ///
+    /// Without colors, it is equivalent to `{string:<width$}`.
+    fn align_left(string: &str, width: usize) -> Cow<'_, str> {
+        let padding_len = width.saturating_sub(Self::visible_width(string));
+        if padding_len == 0 {
+            return Cow::Borrowed(string);
+        }
+        Cow::Owned(format!("{string}{}", " ".repeat(padding_len)))
+    }
+
+    /// Right-align string, ignoring ANSI color sequences.
+    ///
+    /// Without colors, it is equivalent to `{string:>width$}`.
+    fn align_right(string: &str, width: usize) -> Cow<'_, str> {
+        let padding_len = width.saturating_sub(Self::visible_width(string));
+        if padding_len == 0 {
+            return Cow::Borrowed(string);
+        }
+        Cow::Owned(format!("{}{string}", " ".repeat(padding_len)))
+    }
+
+    /// Center-align string, ignoring ANSI color sequences.
+    ///
+    /// Without colors, it is equivalent to `{string:^width$}`.
+    fn align_center(string: &str, width: usize) -> Cow<'_, str> {
+        let padding_len = width.saturating_sub(Self::visible_width(string));
+        if padding_len == 0 {
+            return Cow::Borrowed(string);
+        }
+        // `{string:^width$}` is left-biased if odd.
+        let padding_left = padding_len / 2;
+        let padding_right = padding_len - padding_left;
+        Cow::Owned(format!(
+            "{}{string}{}",
+            " ".repeat(padding_left),
+            " ".repeat(padding_right)
+        ))
+    }
+
+    /// Trim whitespace around a cell, and inside its colors.
+    ///
+    /// Returns `None` if the cell is already trimmed.
+    fn trim_cell<'b>(cell: &Cow<'b, str>) -> Option<Cow<'b, str>> {
+        let trimmed = cell.trim();
+        if let Some((opening, text, reset)) = Self::split_colors(trimmed) {
+            if text.trim().len() != text.len() {
+                return Some(Cow::Owned(format!("{opening}{}{reset}", text.trim())));
+            }
+        }
+        if trimmed.len() == cell.len() {
+            return None;
+        }
+        match cell {
+            Cow::Borrowed(cell) => Some(Cow::Borrowed(cell.trim())),
+            Cow::Owned(_) => Some(Cow::Owned(trimmed.to_string())),
+        }
+    }
+
+    /// Remove non-printing characters from a cell, keeping ANSI
+    /// sequences.
+    ///
+    /// Returns `None` if there is nothing to remove.
+    fn sanitize_cell<'b>(cell: &Cow<'b, str>) -> Option<Cow<'b, str>> {
+        let mut out = String::new();
+        let mut is_modified = false;
+        let mut chars = cell.char_indices().peekable();
+        while let Some((i, char)) = chars.next() {
+            if char == '\x1b' && matches!(chars.peek(), Some((_, '['))) {
+                let sequence_len = cell[i + 2..]
+                    .find(Self::is_csi_final_byte)
+                    .map_or(cell.len() - i, |len| len + 3);
+                out.push_str(&cell[i..i + sequence_len]);
+                while chars.next_if(|(j, _)| *j < i + sequence_len).is_some() {}
+                continue;
+            }
+            if Self::is_non_printing(char) {
+                is_modified = true;
+                continue;
+            }
+            out.push(char);
+        }
+        is_modified.then_some(Cow::Owned(out))
+    }
+
+    fn is_non_printing(char: char) -> bool {
+        (char.is_control() && char != '\t' && char != '\n')
+            || matches!(
+                char,
+                '\u{ad}'
+                    | '\u{200b}'..='\u{200f}'
+                    | '\u{202a}'..='\u{202e}'
+                    | '\u{2060}'..='\u{2064}'
+                    | '\u{feff}'
+            )
+    }
+
+    /// Split a colored string into its leading sequences, its text, and
+    /// its trailing reset.
+    ///
+    /// Returns `None` unless the string starts with an ANSI sequence and
+    /// ends with a reset.
+    fn split_colors(string: &str) -> Option<(&str, &str, &str)> {
+        let without_reset = string.strip_suffix(ANSI_RESET)?;
+        let mut opening_len = 0;
+        while without_reset[opening_len..].starts_with("\x1b[") {
+            let parameters = &without_reset[opening_len + 2..];
+            let sequence_len = parameters.find(Self::is_csi_final_byte)? + 3;
+            opening_len += sequence_len;
+        }
+        if opening_len == 0 {
+            return None;
+        }
+        let (opening, text) = without_reset.split_at(opening_len);
+        Some((opening, text, ANSI_RESET))
+    }
+
+    /// Number of visible characters in a string.
+    ///
+    /// ANSI color sequences are stripped first, then grapheme clusters
+    /// are counted: combining marks, variation selectors, and zero-width
+    /// joiner sequences attach to the preceding character, and regional
+    /// indicators pair up into flags (e.g., `"e\u{301}"` counts as one).
+    ///
+    /// This is an approximation of the Unicode segmentation rules that
+    /// covers the common cases, without pulling in the Unicode tables.
+    fn visible_width(string: &str) -> usize {
+        let mut graphemes = GraphemeCounter::default();
+        Self::strip_ansi_colors(string)
+            .chars()
+            .filter(|char| graphemes.starts_cluster(*char))
+            .count()
+    }
+
+    /// Width of a cell, that of its widest line.
+    fn cell_width(cell: &str) -> usize {
+        cell.split('\n').map(Self::visible_width).max().unwrap_or(0)
+    }
+
+    fn glyphs(&self) -> &'static Glyphs {
+        if self.flags.contains(Flags::ASCII_SAFE) {
+            &ASCII_GLYPHS
+        } else {
+            &UNICODE_GLYPHS
+        }
+    }
+
+    /// Truncate string to `width` visible characters, ending with
+    /// `marker` (e.g., `…`).
+    ///
+    /// ANSI sequences do not count towards the width, and are kept. If
+    /// the string contains any, a reset is appended to the truncated
+    /// string, in case the closing sequence got cut off.
+    ///
+    /// If `width` is too small for the whole marker, the marker itself
+    /// is truncated. Each line of a multi-line string is truncated on
+    /// its own.
+    fn truncate<'b>(string: &'b str, width: usize, marker: &'b str) -> Cow<'b, str> {
+        if string.contains('\n') {
+            let lines: Vec<Cow<str>> = string
+                .split('\n')
+                .map(|line| Self::truncate(line, width, marker))
+                .collect();
+            if lines.iter().all(|line| matches!(line, Cow::Borrowed(_))) {
+                return Cow::Borrowed(string);
+            }
+            return Cow::Owned(lines.join("\n"));
+        }
+        if Self::visible_width(string) <= width {
+            return Cow::Borrowed(string);
+        }
+        let marker_width = Self::visible_width(marker);
+        let Some(nb_kept) = width.checked_sub(marker_width) else {
+            return Cow::Owned(marker.chars().take(width).collect());
+        };
+
+        let mut out = String::with_capacity(string.len());
+        let mut graphemes = GraphemeCounter::default();
+        let mut nb_clusters = 0;
+        let mut has_colors = false;
+        let mut chars = string.chars().peekable();
+
+        while let Some(char) = chars.next() {
+            if char == '\x1b' && chars.peek() == Some(&'[') {
+                has_colors = true;
+                out.push(char);
+                out.extend(chars.next());
+                for char in chars.by_ref() {
+                    out.push(char);
+                    if Self::is_csi_final_byte(char) {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if graphemes.starts_cluster(char) {
+                if nb_clusters == nb_kept {
+                    break;
+                }
+                nb_clusters += 1;
+            }
+            out.push(char);
+        }
+
+        out.push_str(marker);
+        if has_colors {
+            out.push_str(ANSI_RESET);
+        }
+        Cow::Owned(out)
+    }
+
+    /// Whether a character extends the preceding grapheme cluster.
+    fn is_grapheme_extender(char: char) -> bool {
+        matches!(
+            char,
+            // Combining Diacritical Marks (and Extended, Supplement).
+            '\u{0300}'..='\u{036f}'
+                | '\u{1ab0}'..='\u{1aff}'
+                | '\u{1dc0}'..='\u{1dff}'
+                // Combining Diacritical Marks for Symbols.
+                | '\u{20d0}'..='\u{20ff}'
+                // Combining Half Marks.
+                | '\u{fe20}'..='\u{fe2f}'
+                // Zero Width Joiner.
+                | '\u{200d}'
+                // Variation Selectors (and Supplement).
+                | '\u{fe00}'..='\u{fe0f}'
+                | '\u{e0100}'..='\u{e01ef}'
+                // Emoji skin tone modifiers.
+                | '\u{1f3fb}'..='\u{1f3ff}'
+                // Tags (used in subdivision flags).
+                | '\u{e0020}'..='\u{e007f}'
+        )
+    }
+
+    /// Byte index of the first `needle` outside ANSI sequences.
+    fn find_outside_ansi(string: &str, needle: char) -> Option<usize> {
+        let mut chars = string.char_indices().peekable();
+        while let Some((i, char)) = chars.next() {
+            if char == '\x1b' && chars.peek().is_some_and(|(_, char)| *char == '[') {
+                chars.next();
+                for (_, char) in chars.by_ref() {
+                    if Self::is_csi_final_byte(char) {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if char == needle {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Whether a character ends a CSI sequence (`\x1b[...`).
+    fn is_csi_final_byte(char: char) -> bool {
+        matches!(char, '\x40'..='\x7e')
+    }
+
+    /// Remove ANSI color sequences from strings.
+    ///
+    /// This function considers any sequence starting with `\x1b[`, up
+    /// until the first final byte (`@` to `~`, e.g., `m` for colors, `K`
+    /// to erase the line, or `H` to move the cursor), an ANSI sequence.
+    /// This is the general form of CSI sequences. It is naive, in the
+    /// sense that it won't bother to check whether se sequence is
+    /// terminated, or even valid. Basically, `\x1b[` starts stripping,
+    /// and a final byte ends stripping. It's on the caller to only pass
+    /// in valid sequences.
+    ///
+    /// This function delays allocation _until necessary_. As long as
+    /// the output matches the input (no ANSI sequence encountered), it
+    /// will not allocate memory. But, as soon as the output differs
+    /// from the input (ANSI sequence characters need to be removed),
+    /// memory for a new `String` will be allocated. This optimization
+    /// may seem far-fetched, but in the large majority of cases there
+    /// may be a lot of strings to process, but they most probably won't
+    /// be colored. So we make sure to save the overhead.
+    fn strip_ansi_colors(string: &str) -> Cow<'_, str> {
+        enum State {
+            NotInSequence,
+            InSequence,
+        }
+
+        let mut state = State::NotInSequence;
+        let mut chars = string.chars().enumerate().peekable();
+
+        // Don't allocate just yet.
+        let mut output_matches_input = true;
+        let mut out = String::new();
+
+        #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
+        while let Some((i, char)) = chars.next() {
+            match (char, &state) {
+                ('\x1b', State::NotInSequence) => {
+                    if let Some((_, char)) = chars.peek() {
+                        if *char == '[' {
+                            state = State::InSequence;
+                            chars.next(); // `[` would be taken for a final byte.
+
+                            // From now on, input and output differ.
+                            if output_matches_input {
+                                output_matches_input = false;
+                                // The shortest sequence is 3 chars (`\x1b[K`).
+                                out.reserve_exact(string.len().saturating_sub(3));
+                                out = string.chars().take(i).collect();
+                            }
+
+                            continue;
+                        }
+                    }
+                }
+                (char, State::InSequence) if Self::is_csi_final_byte(char) => {
+                    state = State::NotInSequence;
+                    continue;
+                }
+                (_, State::NotInSequence) => {}
+                (_, State::InSequence) => continue,
+            }
+            // As long as the output matches the input, this is a no-op.
+            if output_matches_input {
+                continue;
+            }
+            out.push(char);
+        }
+
+        if output_matches_input {
+            Cow::Borrowed(string)
+        } else {
+            Cow::Owned(out)
+        }
+    }
+
+    fn make_table_blueprint(&self) -> TableBlueprint<'_> {
+        self.try_make_table_blueprint()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_make_table_blueprint(&self) -> Result<TableBlueprint<'_>, TableError> {
+        self.try_prepare().map(|prepared| prepared.table)
+    }
+
+    fn try_prepare(&self) -> Result<Prepared<'_>, TableError> {
+        let nb_cols = self.determine_nb_columns()?;
+
+        let mut headers = self.get_headers_or_default(nb_cols);
+        let mut alignments = self.get_alignments_or_default(nb_cols);
+        let mut data = self.get_data_or_default();
+
+        if self.flags.contains(Flags::SANITIZE_CELLS) {
+            for header in &mut headers {
+                if let Some(sanitized) = Self::sanitize_cell(header) {
+                    *header = sanitized;
+                }
+            }
+        }
+        self.clean_rows(&mut data, nb_cols);
+
+        Self::ensure_data_consistency(&headers, &alignments, &data)?;
+        self.ensure_columns_in_range(nb_cols)?;
+
+        let row_origins = self.sort_rows(&mut data);
+        let nb_data_rows = data.len();
+        self.format_rows(&mut data);
+        let mut split_widths = vec![SplitWidths::default(); self.nb_split_aligned_columns()];
+        self.align_columns(&mut data, &mut split_widths);
+        let mut origins = self.lay_out_columns(&mut headers, &mut alignments, &mut data);
+
+        if let Some(separators) = self.column_separators {
+            if separators.len() != headers.len().saturating_sub(1) {
+                return Err(TableError::SeparatorsMismatch);
+            }
+        }
+
+        // The row numbers column is not part of the data.
+        let nb_user_cols = headers.len() - usize::from(self.flags.contains(Flags::ROW_NUMBERS));
+        let summary = self
+            .summary_if_over
+            .filter(|(max_rows, _)| data.len() > *max_rows)
+            .map(|(_, template)| {
+                template
+                    .replace("{rows}", &group_digits(&data.len().to_string(), ','))
+                    .replace("{cols}", &nb_user_cols.to_string())
+            });
+
+        if let Some(max_columns) = self.max_columns {
+            Self::apply_max_columns(
+                &mut headers,
+                &mut alignments,
+                &mut data,
+                max_columns,
+                self.glyphs().truncation_marker,
+            );
+            if origins.len() > headers.len() {
+                origins.truncate(headers.len() - 1);
+                origins.push(None); // The `…` column.
+            }
+        }
+
+        if self.flags.contains(Flags::ESCAPE_SEPARATOR_COLLISIONS) {
+            self.escape_collisions(&mut headers, &mut data);
+        }
+
+        let mut rows = self.make_rows(data, &row_origins);
+
+        if let Some(max_rows) = self.max_rows {
+            #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
+            {
+                let pinned = self.pinned_positions(&row_origins);
+                rows = Self::apply_max_rows(rows, max_rows, &pinned);
+            }
+        }
+
+        let mut table = self.make_blueprint(headers, alignments, rows, summary);
+        let content_widths = table.columns_width.clone();
+        let fixed_widths = self.rendered_fixed_widths(&origins);
+        self.apply_width_limits(&mut table, fixed_widths.as_deref());
+
+        Ok(Prepared {
+            source: self,
+            table,
+            nb_columns: nb_cols,
+            nb_data_rows,
+            content_widths,
+            split_widths,
+            fixed_widths,
+        })
+    }
+
+    /// Positions of the pinned data rows, after sorting.
+    ///
+    /// `row_origins` holds the index of each data row before sorting.
+    fn pinned_positions(&self, row_origins: &[usize]) -> Vec<usize> {
+        let pins = self.pin_rows.unwrap_or_default();
+        if self.flags.contains(Flags::TRANSPOSE) {
+            // Data rows are now columns, rows are left as they are.
+            return pins.to_vec();
+        }
+        (0..row_origins.len())
+            .filter(|&position| pins.contains(&row_origins[position]))
+            .collect()
+    }
+
+    /// Sanitize, trim, and pad or truncate data rows, as set.
+    fn clean_rows(&self, data: &mut [Cells], nb_cols: usize) {
+        for row in data {
+            if self.flags.contains(Flags::SANITIZE_CELLS) {
+                Self::update_cells(row, |_, cell| Self::sanitize_cell(cell));
+            }
+            if self.flags.contains(Flags::TRIM_CELLS)
+                && !self.flags.contains(Flags::PRESERVE_CELL_WHITESPACE)
+            {
+                Self::update_cells(row, |_, cell| Self::trim_cell(cell));
+            }
+            if (self.flags.contains(Flags::PAD_SHORT_ROWS) && row.len() < nb_cols)
+                || (self.flags.contains(Flags::TRUNCATE_LONG_ROWS) && row.len() > nb_cols)
+            {
+                row.to_mut().resize(nb_cols, Cow::Borrowed(""));
+            }
+        }
+    }
+
+    /// Ensure the columns settings refer to are in range.
+    fn ensure_columns_in_range(&self, nb_cols: usize) -> Result<(), TableError> {
+        let columns = iter::empty()
+            .chain(self.sort_by_column.map(|(column, _)| column))
+            .chain(
+                self.group_numeric
+                    .into_iter()
+                    .flat_map(|(columns, _)| columns)
+                    .copied(),
+            )
+            .chain(self.bar_columns.iter().map(|bar| bar.column))
+            .chain(self.decimal_align.unwrap_or_default().iter().copied())
+            .chain(self.align_on.iter().map(|(column, _)| *column))
+            .chain(self.columns.unwrap_or_default().iter().copied());
+        for index in columns {
+            if index >= nb_cols {
+                return Err(TableError::ColumnOutOfRange {
+                    index,
+                    nb_columns: nb_cols,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Sort data rows, if set.
+    ///
+    /// Returns the index each data row had before sorting.
+    fn sort_rows(&self, data: &mut Vec<Cells>) -> Vec<usize> {
+        let mut row_origins: Vec<usize> = (0..data.len()).collect();
+        if let Some((column, order)) = self.sort_by_column {
+            let mut indexed: Vec<(usize, Cells)> =
+                row_origins.into_iter().zip(data.drain(..)).collect();
+            indexed.sort_by(|(_, a), (_, b)| {
+                let ordering = Self::compare_cells(&a[column], &b[column]);
+                match order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            });
+            (row_origins, *data) = indexed.into_iter().unzip();
+        }
+        row_origins
+    }
+
+    /// Format the values of data cells: digit grouping, bars, and
+    /// maximum cell width.
+    fn format_rows(&self, data: &mut [Cells]) {
+        if let Some((columns, separator)) = self.group_numeric {
+            for &column in columns {
+                Self::apply_group_numeric(data, column, separator);
+            }
+        }
+
+        for bar in &self.bar_columns {
+            for row in data.iter_mut() {
+                row.to_mut()[bar.column] =
+                    Cow::Owned(Self::format_bar(&row[bar.column], bar, self.glyphs().bar));
+            }
+        }
+
+        if let Some(max_cell_width) = self.max_cell_width {
+            self.apply_max_cell_width(data, max_cell_width);
+        }
+    }
+
+    /// Line up the values of columns aligned on a character.
+    ///
+    /// `widths` holds the widths of the aligned columns, decimal ones
+    /// first.
+    fn align_columns(&self, data: &mut [Cells], widths: &mut [SplitWidths]) {
+        let decimal_align = self.decimal_align.unwrap_or_default();
+        let (decimal_widths, align_on_widths) = widths.split_at_mut(decimal_align.len());
+        for (&column, widths) in decimal_align.iter().zip(decimal_widths) {
+            Self::apply_decimal_align(data, column, widths);
+        }
+        for (&(column, separator), widths) in self.align_on.iter().zip(align_on_widths) {
+            Self::apply_align_on(data, column, separator, widths);
+        }
+    }
+
+    /// Select, reorder, transpose, and number columns, as set.
+    ///
+    /// Returns the index each rendered column had before selection, if
+    /// any.
+    fn lay_out_columns<'b>(
+        &'b self,
+        headers: &mut Vec<Cow<'b, str>>,
+        alignments: &mut Vec<fmt::Alignment>,
+        data: &mut Vec<Cells<'b>>,
+    ) -> Vec<Option<usize>> {
+        let mut origins: Vec<Option<usize>> = (0..headers.len()).map(Some).collect();
+
+        if let Some(columns) = self.columns {
+            *headers = Self::select_columns(headers, columns);
+            *alignments = Self::select_columns(alignments, columns);
+            origins = Self::select_columns(&origins, columns);
+        }
+        if self.flags.contains(Flags::REVERSE_COLUMNS) {
+            headers.reverse();
+            alignments.reverse();
+            origins.reverse();
+        }
+        for row in data.iter_mut() {
+            self.arrange_row(row);
+        }
+
+        if self.flags.contains(Flags::TRANSPOSE) {
+            Self::apply_transpose(headers, alignments, data);
+            origins = vec![None; headers.len()];
+        }
+
+        if self.flags.contains(Flags::ROW_NUMBERS) {
+            let header = if headers.iter().all(|header| header.is_empty()) {
+                ""
+            } else {
+                self.row_numbers_header
+            };
+            headers.insert(0, Cow::Borrowed(header));
+            alignments.insert(0, fmt::Alignment::Right);
+            origins.insert(0, None);
+            Self::number_rows(data, 1);
+        }
+
+        origins
+    }
+
+    fn nb_split_aligned_columns(&self) -> usize {
+        self.decimal_align.unwrap_or_default().len() + self.align_on.len()
+    }
+
+    /// Select and reorder the cells of a data row, as set.
+    fn arrange_row(&self, row: &mut Cells) {
+        if let Some(columns) = self.columns {
+            *row = Cells::Owned(Self::select_columns(row, columns));
+        }
+        if self.flags.contains(Flags::REVERSE_COLUMNS) {
+            row.to_mut().reverse();
+        }
+    }
+
+    /// Prepend the number of each row, starting at `first`.
+    fn number_rows(data: &mut [Cells], first: usize) {
+        for (i, row) in data.iter_mut().enumerate() {
+            row.to_mut().insert(0, Cow::Owned((first + i).to_string()));
+        }
+    }
+
+    /// Escape or mark the column separators appearing in cells.
+    fn escape_collisions(&self, headers: &mut [Cow<str>], data: &mut [Cells]) {
+        let separators = self.colliding_separators();
+        for header in headers {
+            if let Some(escaped) = self.escape_separator_collisions_in(header, &separators) {
+                *header = Cow::Owned(escaped);
+            }
+        }
+        for row in data {
+            Self::update_cells(row, |_, cell| {
+                self.escape_separator_collisions_in(cell, &separators)
+                    .map(Cow::Owned)
+            });
+        }
+    }
+
+    fn make_blueprint<'b>(
+        &'b self,
+        headers: Vec<Cow<'b, str>>,
+        alignments: Vec<fmt::Alignment>,
+        rows: Vec<Row<'b>>,
+        summary: Option<String>,
+    ) -> TableBlueprint<'b> {
+        let columns_width = self
+            .columns_width
+            .0
+            .get_or_init(|| Self::determine_columns_width(&headers, &rows))
+            .clone();
+        let column_separator = self
+            .column_separator
+            .as_deref()
+            .unwrap_or(DEFAULT_COLUMN_SEPARATOR);
+
+        TableBlueprint {
+            headers,
+            alignments,
+            rows,
+            columns_width,
+            column_separator,
+            column_separators: self.column_separators,
+            zebra: self.zebra,
+            margin_left: self.margin_left,
+            legend: self.legend.unwrap_or_default(),
+            caption: self.caption,
+            now_fn: self.flags.contains(Flags::TIMESTAMP_CAPTION).then(|| {
+                self.now_fn
+                    .map_or(Self::now_utc as fn() -> String, |clock| clock.0)
+            }),
+            borders: self.borders,
+            pad_last_column: self.borders,
+            flags: self.flags,
+            glyphs: self.glyphs(),
+            max_output_bytes: self.max_output_bytes,
+            cell_padding: self.cell_padding,
+            header_style: self.header_style,
+            cell_formatter: self.cell_formatter.map(|formatter| formatter.0),
+            vertical_align: self.vertical_align,
+            elision_alignment: self.elision_alignment,
+            line_ending: self.line_ending,
+            summary,
+        }
+    }
+
+    /// Fixed width of each rendered column, if any are set.
+    ///
+    /// `origins` holds the index each rendered column had before
+    /// selection, if any.
+    fn rendered_fixed_widths(&self, origins: &[Option<usize>]) -> Option<Vec<Option<usize>>> {
+        self.fixed_column_widths.map(|fixed_widths| {
+            origins
+                .iter()
+                .map(|origin| origin.and_then(|origin| fixed_widths.get(origin).copied().flatten()))
+                .collect()
+        })
+    }
+
+    /// Apply fixed column widths, then fit the table in `max_width`.
+    fn apply_width_limits(
+        &self,
+        table: &mut TableBlueprint,
+        fixed_widths: Option<&[Option<usize>]>,
+    ) {
+        if let Some(fixed_widths) = fixed_widths {
+            for (width, fixed_width) in table.columns_width.iter_mut().zip(fixed_widths) {
+                if let Some(fixed_width) = fixed_width {
+                    *width = *fixed_width;
+                }
+            }
+            table.truncate_to_columns_width();
+        }
+
+        if let Some(max_width) = self.max_width {
+            let width = max_width.saturating_sub(self.margin_left);
+            table.shrink_to(width);
+            if self.flags.contains(Flags::STRETCH_LAST_COLUMN) {
+                table.stretch_last_column_to(width);
+            }
+        }
+    }
+
+    #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
+    fn determine_nb_columns(&self) -> Result<usize, TableError> {
+        if let Some(headers) = self.headers.as_ref() {
+            return Ok(headers.len());
+        }
+        if let Some(data) = self.data.as_ref() {
+            if self.flags.contains(Flags::PAD_SHORT_ROWS) {
+                if let Some(nb_cols) = data.iter().map(Vec::len).max() {
+                    return Ok(nb_cols);
+                }
+            } else if !data.is_empty() {
+                return Ok(data[0].len());
+            }
+        }
+        Err(TableError::Empty)
+    }
+
+    /// Compare cells numerically if both are numbers, else as text.
+    ///
+    /// Numbers come before text, for the order to be total. Only finite
+    /// numbers count, `NaN` or `inf` are text (e.g., names).
+    fn compare_cells(a: &str, b: &str) -> Ordering {
+        let (a, b) = (Self::strip_ansi_colors(a), Self::strip_ansi_colors(b));
+        let number = |cell: &str| cell.trim().parse::<f64>().ok().filter(|x| x.is_finite());
+        match (number(&a), number(&b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.cmp(&b),
+        }
+    }
+
+    /// Project values through a list of column indices.
+    fn select_columns<T: Clone>(values: &[T], columns: &[usize]) -> Vec<T> {
+        columns.iter().map(|&index| values[index].clone()).collect()
+    }
+
+    fn get_headers_or_default(&self, nb_cols: usize) -> Vec<Cow<'_, str>> {
+        match self.headers.as_ref() {
+            Some(headers) => headers
+                .iter()
+                .map(|header| Self::apply_header_case(header, self.header_case))
+                .collect(),
+            // This may look a bit hacky (it is), but it plays nicely
+            // with the overall logic (`Option` would make the code too
+            // convoluted). Moreover, it has the added benefit of
+            // handling the special case where the user does it himself.
+            None => vec![Cow::Borrowed(""); nb_cols],
+        }
+    }
+
+    /// Change the case of a header, leaving ANSI sequences intact.
+    fn apply_header_case(header: &str, case: HeaderCase) -> Cow<'_, str> {
+        if case == HeaderCase::AsIs {
+            return Cow::Borrowed(header);
+        }
+
+        let mut out = String::with_capacity(header.len());
+        let mut chars = header.chars().peekable();
+        let mut is_word_start = true;
+
+        while let Some(char) = chars.next() {
+            if char == '\x1b' && chars.peek() == Some(&'[') {
+                // Copy the sequence verbatim, up to and including the
+                // final byte.
+                out.push(char);
+                out.extend(chars.next());
+                for char in chars.by_ref() {
+                    out.push(char);
+                    if Self::is_csi_final_byte(char) {
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let is_upper = match case {
+                HeaderCase::Upper => true,
+                HeaderCase::Title => is_word_start,
+                HeaderCase::AsIs | HeaderCase::Lower => false,
+            };
+            if is_upper {
+                out.extend(char.to_uppercase());
+            } else {
+                out.extend(char.to_lowercase());
+            }
+            is_word_start = char.is_whitespace();
+        }
+
+        Cow::Owned(out)
+    }
+
+    fn get_alignments_or_default(&self, nb_cols: usize) -> Vec<fmt::Alignment> {
+        match self.alignments.as_deref() {
+            Some(alignments) => {
+                let mut alignments = alignments.to_vec();
+                if alignments.len() < nb_cols {
+                    alignments.resize(nb_cols, fmt::Alignment::Left);
+                }
+                alignments
+            }
+            None => [fmt::Alignment::Left].repeat(nb_cols),
+        }
+    }
+
+    /// Borrow the rows of data, they are cloned only if modified.
+    fn get_data_or_default(&self) -> Vec<Cells<'_>> {
+        match self.data.as_ref() {
+            Some(data) => data.iter().map(|row| Cells::Borrowed(row)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Replace the cells for which `update` returns a new value.
+    ///
+    /// The row is only cloned if a cell is replaced.
+    fn update_cells<'b>(
+        cells: &mut Cells<'b>,
+        mut update: impl FnMut(usize, &Cow<'b, str>) -> Option<Cow<'b, str>>,
+    ) {
+        for i in 0..cells.len() {
+            if let Some(cell) = update(i, &cells[i]) {
+                cells.to_mut()[i] = cell;
+            }
+        }
+    }
+
+    /// Truncate data cells wider than `max_cell_width`.
+    ///
+    /// Columns with [`Overflow::None`] are left untouched.
+    fn apply_max_cell_width(&self, data: &mut [Cells], max_cell_width: usize) {
+        let overflows = self.column_overflow.unwrap_or_default();
+        for row in data {
+            Self::update_cells(row, |i, cell| {
+                let overflow = overflows.get(i).copied().unwrap_or_default();
+                (overflow == Overflow::Truncate && Self::cell_width(cell) > max_cell_width).then(
+                    || {
+                        let marker = self.glyphs().truncation_marker;
+                        Cow::Owned(Self::truncate(cell, max_cell_width, marker).into_owned())
+                    },
+                )
+            });
+        }
+    }
+
+    /// Drop columns on the right to conform to the 'max columns' setting.
+    ///
+    /// The first `max_columns - 1` columns are kept, followed by a `…`
+    /// column indicating there are more.
+    fn apply_max_columns(
+        headers: &mut Vec<Cow<str>>,
+        alignments: &mut Vec<fmt::Alignment>,
+        data: &mut [Cells],
+        max_columns: usize,
+        marker: &'static str,
+    ) {
+        if headers.len() <= max_columns {
+            return; // no-op.
+        }
+
+        let nb_kept = max_columns.saturating_sub(1);
+        let indicator_header = if headers.iter().all(|header| header.is_empty()) {
+            ""
+        } else {
+            marker
+        };
+
+        headers.truncate(nb_kept);
+        headers.push(Cow::Borrowed(indicator_header));
+        alignments.truncate(nb_kept);
+        alignments.push(fmt::Alignment::Left);
+        for row in data {
+            Self::apply_max_columns_to_row(row, max_columns, marker);
+        }
+    }
+
+    /// Drop the cells of a data row on the right, for the 'max columns'
+    /// setting.
+    fn apply_max_columns_to_row(row: &mut Cells, max_columns: usize, marker: &'static str) {
+        if row.len() <= max_columns {
+            return; // no-op.
+        }
+        let row = row.to_mut();
+        row.truncate(max_columns.saturating_sub(1));
+        row.push(Cow::Borrowed(marker));
+    }
+
+    /// Turn a number into a bar, or blank if the cell is not a number.
+    fn format_bar(cell: &str, bar: &BarColumn, [filled, empty]: [char; 2]) -> String {
+        let value = match Self::strip_ansi_colors(cell).trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => value,
+            _ => return String::new(), // Not a number, or `inf`, `NaN`.
+        };
+        // Without a positive `max`, there is no scale to fill bars on.
+        let ratio = if bar.max > 0.0 {
+            (value / bar.max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let width = f64::from(u32::try_from(bar.width).unwrap_or(u32::MAX));
+        // `ratio` is within `0..=1`, so this is a whole number within
+        // `0..=width`, it is neither negative nor out of range.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let nb_filled = ((ratio * width).round() as usize).min(bar.width);
+
+        let mut out = String::with_capacity(bar.width * filled.len_utf8());
+        out.extend(iter::repeat_n(filled, nb_filled));
+        out.extend(iter::repeat_n(empty, bar.width - nb_filled));
+        out
+    }
+
+    fn apply_group_numeric(data: &mut [Cells], column: usize, separator: char) {
+        for row in data {
+            if let Cow::Owned(grouped) = group_digits(&row[column], separator) {
+                row.to_mut()[column] = Cow::Owned(grouped);
+            }
+        }
+    }
+
+    /// Pad numbers in a column for their decimal points to line up.
+    ///
+    /// Integer parts are right-aligned, and fractional parts are
+    /// left-aligned, so all numbers end up the same width. Numbers
+    /// without a fractional part get padding in place of it. Text cells
+    /// are left untouched.
+    fn apply_decimal_align(data: &mut [Cells], column: usize, widths: &mut SplitWidths) {
+        Self::apply_split_align(data, column, '.', Self::split_decimal, widths);
+    }
+
+    /// Pad the cells of a column for `separator` to line up.
+    ///
+    /// Cells without `separator` are aligned as if it were at the end.
+    /// Separators inside ANSI sequences do not count.
+    fn apply_align_on(
+        data: &mut [Cells],
+        column: usize,
+        separator: char,
+        widths: &mut SplitWidths,
+    ) {
+        Self::apply_split_align(
+            data,
+            column,
+            separator,
+            |cell| {
+                Some(match Self::find_outside_ansi(cell, separator) {
+                    Some(i) => (&cell[..i], Some(&cell[i + separator.len_utf8()..])),
+                    None => (cell, None),
+                })
+            },
+            widths,
+        );
+    }
+
+    /// Pad the cells of a column for the point where `split()` splits
+    /// them to line up.
+    ///
+    /// Prefixes are right-aligned, and suffixes are left-aligned, so all
+    /// split cells end up the same width. Cells without a suffix get
+    /// padding in place of it. Cells that `split()` rejects are left
+    /// untouched.
+    ///
+    /// `widths` are the minimum widths to pad to, they are updated with
+    /// the widths of the cells.
+    fn apply_split_align(
+        data: &mut [Cells],
+        column: usize,
+        separator: char,
+        split: impl Fn(&str) -> Option<(&str, Option<&str>)>,
+        widths: &mut SplitWidths,
+    ) {
+        let SplitWidths {
+            prefix: mut prefix_width,
+            suffix: mut suffix_width,
+        } = *widths;
+        for row in data.iter() {
+            if let Some((prefix, suffix)) = split(&row[column]) {
+                prefix_width = prefix_width.max(Self::visible_width(prefix));
+                if let Some(suffix) = suffix {
+                    let width = Self::visible_width(suffix);
+                    suffix_width = Some(suffix_width.unwrap_or(0).max(width));
+                }
+            }
+        }
+        *widths = SplitWidths {
+            prefix: prefix_width,
+            suffix: suffix_width,
+        };
+
+        let separator_width = Self::visible_width(separator.encode_utf8(&mut [0; 4]));
+        for row in data {
+            let Some((prefix, suffix)) = split(&row[column]) else {
+                continue;
+            };
+            let prefix = Self::align_right(prefix, prefix_width);
+            let suffix = match (suffix, suffix_width) {
+                (Some(suffix), Some(width)) => {
+                    format!("{separator}{}", Self::align_left(suffix, width))
+                }
+                (None, Some(width)) => " ".repeat(width + separator_width),
+                (_, None) => String::new(),
+            };
+            let aligned = format!("{prefix}{suffix}");
+            if aligned != row[column] {
+                row.to_mut()[column] = Cow::Owned(aligned);
+            }
+        }
+    }
+
+    /// Split a number into its integer and fractional parts.
+    ///
+    /// Returns `None` if the cell, ANSI sequences aside, is not a number
+    /// (an optional sign, digits possibly grouped with `,`, and an
+    /// optional `.` and digits).
+    fn split_decimal(cell: &str) -> Option<(&str, Option<&str>)> {
+        let number = Self::strip_ansi_colors(cell);
+        let unsigned = number.strip_prefix(['-', '+']).unwrap_or(&number);
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (unsigned, ""),
+        };
+        let is_number = integer.starts_with(|char: char| char.is_ascii_digit())
+            && integer
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || byte == b',')
+            && fraction.bytes().all(|byte| byte.is_ascii_digit());
+        if !is_number {
+            return None;
+        }
+
+        // ANSI sequences never contain `.`, split the original cell.
+        match cell.split_once('.') {
+            Some((integer, fraction)) => Some((integer, Some(fraction))),
+            None => Some((cell, None)),
+        }
+    }
+
+    /// Separators that cells must not contain, as seen in the output.
+    fn colliding_separators(&self) -> Vec<&str> {
+        let separators: Vec<&str> = if self.borders {
+            vec![self.glyphs().bordered_column_separator.trim()]
+        } else if let Some(separators) = self.column_separators {
+            separators.to_vec()
+        } else {
+            vec![self
+                .column_separator
+                .as_deref()
+                .unwrap_or(DEFAULT_COLUMN_SEPARATOR)]
+        };
+        separators
+            .into_iter()
+            .filter(|separator| !separator.is_empty())
+            .collect()
+    }
+
+    /// Escape or mark the column separators appearing inside a cell.
+    ///
+    /// Returns `None` if the cell contains no separator.
+    fn escape_separator_collisions_in(&self, cell: &str, separators: &[&str]) -> Option<String> {
+        let mut escaped: Option<String> = None;
+        for separator in separators {
+            let current = escaped.as_deref().unwrap_or(cell);
+            if current.contains(separator) {
+                let replacement = match self.collision_marker {
+                    Some(marker) => Cow::Borrowed(marker),
+                    None => Cow::Owned(format!("\\{separator}")),
+                };
+                escaped = Some(current.replace(separator, &replacement));
+            }
+        }
+        escaped
+    }
+
+    /// Turn headers into the first column, and rows into columns.
+    ///
+    /// The new headers are empty, so no header line is rendered. Labels
+    /// and values are all left-aligned.
+    fn apply_transpose<'b>(
+        headers: &mut Vec<Cow<'b, str>>,
+        alignments: &mut Vec<fmt::Alignment>,
+        data: &mut Vec<Cells<'b>>,
+    ) {
+        let nb_cols = data.len() + 1;
+        let mut columns: Vec<_> = data
+            .drain(..)
+            .map(|row| row.into_owned().into_iter())
+            .collect();
+        *data = headers
+            .drain(..)
+            .map(|header| {
+                let mut row = Vec::with_capacity(nb_cols);
+                row.push(header);
+                row.extend(columns.iter_mut().filter_map(Iterator::next));
+                Cells::Owned(row)
+            })
+            .collect();
+
+        *headers = vec![Cow::Borrowed(""); nb_cols];
+        *alignments = vec![fmt::Alignment::Left; nb_cols];
+    }
+
+    /// Interleave span, section, and rule rows with data rows.
+    ///
+    /// `row_origins` holds the index of each data row before sorting.
+    fn make_rows<'b>(&'b self, data: Vec<Cells<'b>>, row_origins: &[usize]) -> Vec<Row<'b>> {
+        if self.flags.contains(Flags::TRANSPOSE) {
+            // Inserted rows refer to data rows, which are now columns.
+            return data
+                .into_iter()
+                .enumerate()
+                .map(|(i, cells)| Row::Cells(cells, i))
+                .collect();
+        }
+        let mut inserted_rows = self.inserted_rows.iter().peekable();
+        let group_boundaries = self.group_boundaries.unwrap_or_default();
+        let mut rows = Vec::with_capacity(data.len() + self.inserted_rows.len());
+
+        for (i, cells) in data.into_iter().enumerate() {
+            while let Some((_, row)) = inserted_rows.next_if(|(pos, _)| *pos <= i) {
+                rows.push(row.clone());
+            }
+            if i > 0 && group_boundaries.contains(&row_origins[i]) {
+                rows.push(Row::Rule);
+            }
+            rows.push(Row::Cells(cells, i));
+        }
+        rows.extend(inserted_rows.map(|(_, row)| row.clone()));
+
+        rows
+    }
+
+    /// Ensure data is consistent.
+    ///
+    /// "Consistent" essentially means the number of headers matches
+    /// the number of alignment properties, and the number of columns
+    /// in the data.
+    fn ensure_data_consistency(
+        headers: &[Cow<str>],
+        alignments: &[fmt::Alignment],
+        data: &[Cells],
+    ) -> Result<(), TableError> {
+        if headers.len() != alignments.len() {
+            return Err(TableError::AlignmentsMismatch);
+        }
+        if !data.iter().all(|row| row.len() == headers.len()) {
+            return Err(TableError::ColumnsMismatch);
+        }
+        Ok(())
+    }
+
+    /// Drop rows in the middle to conform to the 'max rows' setting.
+    ///
+    /// Only data rows count towards the limit. Pinned data rows are
+    /// always kept, and the rest of the limit goes to the other rows,
+    /// split between head and tail. Each run of dropped rows is replaced
+    /// by an elision row. Span and section rows are kept if they sit
+    /// next to a kept data row, and dropped if they sit in the middle of
+    /// dropped rows.
+    fn apply_max_rows<'b>(rows: Vec<Row<'b>>, max_rows: usize, pinned: &[usize]) -> Vec<Row<'b>> {
+        let nb_data_rows = rows
+            .iter()
+            .filter(|row| matches!(row, Row::Cells(..)))
+            .count();
+
+        if nb_data_rows <= max_rows {
+            return rows; // no-op.
+        }
+
+        let mut is_kept = vec![false; nb_data_rows];
+        for &position in pinned.iter().filter(|&&position| position < nb_data_rows) {
+            is_kept[position] = true;
+        }
+        let unpinned: Vec<usize> = (0..nb_data_rows)
+            .filter(|&position| !is_kept[position])
+            .collect();
+        let budget = max_rows.saturating_sub(nb_data_rows - unpinned.len());
+
+        let (nb_head, nb_tail) = match budget {
+            0 => (0, 0),
+            1 => (1, 0),
+            // Bias towards more tail elements.
+            _ => (budget / 2, budget - budget / 2),
+        };
+        for &position in unpinned
+            .iter()
+            .take(nb_head)
+            .chain(unpinned.iter().skip(unpinned.len() - nb_tail))
+        {
+            is_kept[position] = true;
+        }
+
+        let mut kept = Vec::with_capacity(max_rows + 1);
+        let mut has_pending_elision = false;
+        // Number of data rows preceding the current row.
+        let mut position = 0;
+
+        for row in rows {
+            let is_data_row = matches!(row, Row::Cells(..));
+            let is_row_kept = if is_data_row {
+                is_kept[position]
+            } else {
+                position == 0
+                    || position == nb_data_rows
+                    || is_kept[position - 1]
+                    || is_kept[position]
+            };
+
+            if is_row_kept {
+                if has_pending_elision {
+                    kept.push(Row::Elision);
+                    has_pending_elision = false;
+                }
+                kept.push(row);
+            } else if is_data_row {
+                has_pending_elision = true;
+            }
+
+            if is_data_row {
+                position += 1;
+            }
+        }
+
+        if has_pending_elision {
+            kept.push(Row::Elision);
+        }
+
+        kept
+    }
+
+    /// Determine the width of each column.
+    ///
+    /// The width of a column is the number of characters in the longest
+    /// value held in the column (including header). Span and section
+    /// rows do not count.
+    fn determine_columns_width(headers: &[Cow<str>], rows: &[Row]) -> Vec<usize> {
+        let mut cols_width = vec![0; headers.len()];
+        for i in 0..headers.len() {
+            let column_values: Vec<&str> = rows
+                .iter()
+                .filter_map(|row| match row {
+                    Row::Cells(cells, _) => Some(cells[i].as_ref()),
+                    Row::Elision => Some(ELISION_MARKER),
+                    Row::Span(..) | Row::Section(_) | Row::Rule => None,
+                })
+                .collect();
+            let max_width = Self::width_of_longest_value_in_column(&headers[i], &column_values);
+            cols_width[i] = max_width;
+        }
+        cols_width
+    }
+
+    fn width_of_longest_value_in_column(header: &str, column_values: &[&str]) -> usize {
+        let header = iter::once(&header);
+        let column_values = column_values.iter();
+
+        header
+            .chain(column_values)
+            .map(|value| Self::cell_width(value))
+            .max()
+            .expect("iterator cannot be empty because header is required")
+    }
+}
+
+impl TableBlueprint<'_> {
+    fn render(&self, output: &mut dyn fmt::Write) -> fmt::Result {
+        let mut output = Output {
+            inner: output,
+            max_bytes: self.max_output_bytes,
+            nb_bytes: 0,
+            is_exhausted: false,
+            has_pending_newline: false,
+        };
+        self.render_table(&mut output)?;
+        if self.flags.contains(Flags::TRAILING_NEWLINE) && output.has_pending_newline {
+            output.inner.write_str(self.line_ending.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn render_table(&self, output: &mut Output) -> fmt::Result {
+        if let Some(summary) = &self.summary {
+            return self.write_line(output, summary);
+        }
+        self.render_body(output)?;
+        self.render_caption(output)?;
+        self.render_legend(output)
+    }
+
+    fn render_caption(&self, output: &mut Output) -> fmt::Result {
+        if let Some(caption) = self.caption {
+            self.write_line(output, caption)?;
+        }
+        if let Some(now_fn) = self.now_fn {
+            self.write_line(output, &now_fn())?;
+        }
+        Ok(())
+    }
+
+    fn render_body(&self, output: &mut Output) -> fmt::Result {
+        if self.rows.is_empty() && !self.borders {
+            return self.render_headers(output);
+        }
+
+        if self.borders {
+            self.write_line(output, &self.format_rule(self.glyphs.box_chars.top))?;
+        }
+
+        if !self.headers.iter().all(|header| header.is_empty()) {
+            self.render_headers(output)?;
+            if self.borders && !self.rows.is_empty() {
+                self.write_line(output, &self.format_rule(self.glyphs.box_chars.middle))?;
+            }
+        }
+
+        // Columns narrowed by `max_width` may not fit the full marker.
+        let elision_row: Vec<Cow<str>> = self
+            .columns_width
+            .iter()
+            .map(|width| self.truncate(ELISION_MARKER, *width))
+            .collect();
+        let mut nb_styled_rows = 0;
+
+        for row in &self.rows {
+            if output.is_exhausted {
+                break;
+            }
+            let line = match row {
+                Row::Cells(cells, index) => {
+                    nb_styled_rows += 1;
+                    let style = self.zebra_style(nb_styled_rows - 1);
+                    let styled: Vec<Cow<str>>;
+                    let cells: &[Cow<str>] = match self.cell_formatter {
+                        Some(formatter) => {
+                            styled = cells
+                                .iter()
+                                .enumerate()
+                                .map(|(col, cell)| {
+                                    formatter(*index, col, cell).unwrap_or(Cow::Borrowed(cell))
+                                })
+                                .collect();
+                            &styled
+                        }
+                        None => cells,
+                    };
+                    if cells.iter().any(|cell| cell.contains('\n')) {
+                        for line in self.stack_lines(cells) {
+                            self.write_line(output, &self.format_cells(&line, style))?;
+                        }
+                        continue;
+                    }
+                    self.format_cells(cells, style)
+                }
+                Row::Elision => {
+                    nb_styled_rows += 1;
+                    self.format_styled_cells(
+                        &elision_row,
+                        self.zebra_style(nb_styled_rows - 1),
+                        None,
+                        self.elision_alignment,
+                    )
+                }
+                Row::Span(text, alignment) => self.format_span(text, *alignment),
+                Row::Section(title) => self.format_section(title),
+                Row::Rule => self.format_group_rule(),
+            };
+            self.write_line(output, &line)?;
+        }
+
+        if self.borders {
+            self.write_line(output, &self.format_rule(self.glyphs.box_chars.bottom))?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the legend as `symbol — description` lines.
+    ///
+    /// Symbols are padded to the same width, and descriptions are
+    /// word-wrapped to fit the width of the table.
+    fn render_legend(&self, output: &mut Output) -> fmt::Result {
+        let symbols_width = self
+            .legend
+            .iter()
+            .map(|(symbol, _)| Table::visible_width(symbol))
+            .max()
+            .unwrap_or(0);
+        let legend_separator = self.glyphs.legend_separator;
+        let indent = symbols_width + legend_separator.chars().count();
+        let description_width = self.total_width().saturating_sub(indent);
+
+        for (symbol, description) in self.legend {
+            let symbol = Table::align_left(symbol, symbols_width);
+            let mut lines = Table::wrap_words(description, description_width).into_iter();
+
+            let first_line = lines.next().unwrap_or_default();
+            self.write_line(output, &format!("{symbol}{legend_separator}{first_line}"))?;
+            for line in lines {
+                self.write_line(output, &format!("{:indent$}{line}", ""))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split multi-line cells into lines, one table line each.
+    ///
+    /// Cells with fewer lines are padded with blank lines, following
+    /// the vertical alignment.
+    fn stack_lines<'c>(&self, cells: &'c [Cow<str>]) -> Vec<Vec<&'c str>> {
+        let cells_lines: Vec<Vec<&str>> = cells
+            .iter()
+            .map(|cell| cell.split('\n').collect())
+            .collect();
+        let height = cells_lines.iter().map(Vec::len).max().unwrap_or(1);
+
+        (0..height)
+            .map(|i| {
+                cells_lines
+                    .iter()
+                    .map(|lines| {
+                        let offset = match self.vertical_align {
+                            VerticalAlignment::Top => 0,
+                            VerticalAlignment::Middle => (height - lines.len()) / 2,
+                            VerticalAlignment::Bottom => height - lines.len(),
+                        };
+                        i.checked_sub(offset)
+                            .and_then(|i| lines.get(i))
+                            .copied()
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Render the headers, multi-line ones stacked like data cells.
+    fn render_headers(&self, output: &mut Output) -> fmt::Result {
+        if self.headers.iter().any(|header| header.contains('\n')) {
+            for line in self.stack_lines(&self.headers) {
+                self.write_line(output, &self.format_headers(&line))?;
+            }
+            return Ok(());
+        }
+        self.write_line(output, &self.format_headers(&self.headers))
+    }
+
+    fn format_headers(&self, headers: &[impl AsRef<str>]) -> String {
+        self.format_styled_cells(headers, None, self.header_style, None)
+    }
+
+    fn format_cells(&self, cells: &[impl AsRef<str>], style: Option<&str>) -> String {
+        self.format_styled_cells(cells, style, None, None)
+    }
+
+    /// Format a row, with `style` around the row, and `cell_style`
+    /// (prefix, suffix) around each cell, padding included.
+    ///
+    /// If set, `alignment` overrides the alignment of every column.
+    fn format_styled_cells(
+        &self,
+        cells: &[impl AsRef<str>],
+        style: Option<&str>,
+        cell_style: Option<(&str, &str)>,
+        alignment: Option<fmt::Alignment>,
+    ) -> String {
+        let (cell_prefix, cell_suffix) = cell_style.unwrap_or_default();
+        let mut line = String::with_capacity(self.total_width());
+
+        if let Some(style) = style {
+            line.push_str(style);
+        }
+        if self.borders {
+            line.push(self.glyphs.box_chars.vertical);
+            line.push(' ');
+        }
+
+        for (i, cell) in cells.iter().enumerate() {
+            let cell = cell.as_ref();
+            let width = self.columns_width[i];
+            let alignment = alignment.unwrap_or(self.alignments[i]);
+
+            let is_last_column = i == self.headers.len() - 1;
+
+            let colors = if self.flags.contains(Flags::PAD_INSIDE_COLORS) {
+                Table::split_colors(cell)
+            } else {
+                None
+            };
+            let (opening, cell, reset) = colors.unwrap_or(("", cell, ""));
+            let is_trailing_space_trimmed = is_last_column
+                && !self.pad_last_column
+                && colors.is_none()
+                && (alignment == fmt::Alignment::Left
+                    || self.flags.contains(Flags::TRIM_LAST_COLUMN));
+
+            let (padding_left, padding_right) = self.cell_padding;
+            line.push_str(cell_prefix);
+            line.extend(iter::repeat_n(' ', padding_left));
+            line.push_str(opening);
+            match alignment {
+                fmt::Alignment::Left if is_trailing_space_trimmed => line.push_str(cell),
+                fmt::Alignment::Left => line.push_str(&Table::align_left(cell, width)),
+                fmt::Alignment::Right => line.push_str(&Table::align_right(cell, width)),
+                fmt::Alignment::Center if is_trailing_space_trimmed => {
+                    // Left padding of `align_center()`, without the right.
+                    let padding_len = width.saturating_sub(Table::visible_width(cell));
+                    line.extend(iter::repeat_n(' ', padding_len / 2));
+                    line.push_str(cell);
+                }
+                fmt::Alignment::Center => line.push_str(&Table::align_center(cell, width)),
+            }
+            line.push_str(reset);
+            if !is_trailing_space_trimmed {
+                line.extend(iter::repeat_n(' ', padding_right));
+            }
+            line.push_str(cell_suffix);
+
+            if !is_last_column {
+                line.push_str(self.column_separator(i));
+            }
+        }
+
+        if self.borders {
+            line.push(' ');
+            line.push(self.glyphs.box_chars.vertical);
+        }
+        if style.is_some() {
+            line.push_str(ANSI_RESET);
+        }
+
+        line
+    }
+
+    fn format_span(&self, text: &str, alignment: fmt::Alignment) -> String {
+        if self.borders {
+            // Keep the frame intact.
+            let width = self.inner_width().saturating_sub(2);
+            let text = self.truncate(text, width);
+            let text = match alignment {
+                fmt::Alignment::Left => Table::align_left(&text, width),
+                fmt::Alignment::Right => Table::align_right(&text, width),
+                fmt::Alignment::Center => Table::align_center(&text, width),
+            };
+            return format!("{0} {text} {0}", self.glyphs.box_chars.vertical);
+        }
+
+        let width = self.total_width();
+        match alignment {
+            fmt::Alignment::Left => text.to_string(),
+            fmt::Alignment::Right => Table::align_right(text, width).into_owned(),
+            fmt::Alignment::Center => Table::align_center(text, width).into_owned(),
+        }
+    }
+
+    /// Narrow the widest columns until the table fits in `width`.
+    ///
+    /// Cells (headers included) wider than their narrowed column are
+    /// truncated.
+    fn shrink_to(&mut self, width: usize) {
+        let mut is_shrunk = false;
+        while self.total_width() > width {
+            let widest = self
+                .columns_width
+                .iter_mut()
+                .rev() // `max()` returns the last maximum, favor the first.
+                .max();
+            match widest {
+                Some(widest) if *widest > 1 => *widest -= 1,
+                _ => break,
+            }
+            is_shrunk = true;
+        }
+        if is_shrunk {
+            self.truncate_to_columns_width();
+        }
+    }
+
+    /// Truncate a string with the truncation marker of the table.
+    fn truncate<'b>(&self, string: &'b str, width: usize) -> Cow<'b, str> {
+        Table::truncate(string, width, self.glyphs.truncation_marker)
+    }
+
+    /// Truncate the cells (headers included) wider than their column.
+    fn truncate_to_columns_width(&mut self) {
+        let marker = self.glyphs.truncation_marker;
+        let truncate = |cells: &mut [Cow<str>], columns_width: &[usize]| {
+            for (cell, width) in cells.iter_mut().zip(columns_width) {
+                if Table::cell_width(cell) > *width {
+                    *cell = Cow::Owned(Table::truncate(cell, *width, marker).into_owned());
+                }
+            }
+        };
+        truncate(&mut self.headers, &self.columns_width);
+        for row in &mut self.rows {
+            if let Row::Cells(cells, _) = row {
+                let is_too_wide = cells
+                    .iter()
+                    .zip(&self.columns_width)
+                    .any(|(cell, width)| Table::cell_width(cell) > *width);
+                if is_too_wide {
+                    truncate(cells.to_mut(), &self.columns_width);
+                }
+            }
+        }
+    }
+
+    /// Widen the last column until the table fills `width`.
+    fn stretch_last_column_to(&mut self, width: usize) {
+        let slack = width.saturating_sub(self.total_width());
+        if slack == 0 {
+            return;
+        }
+        if let Some(last) = self.columns_width.last_mut() {
+            *last += slack;
+            self.pad_last_column = true;
+        }
+    }
+
+    /// Horizontal rule with the title embedded at the start.
+    ///
+    /// With borders, it is an interior border (`├─ Title ───┤`),
+    /// otherwise it is a banner (`── Title ─────`). If the table is too
+    /// narrow for any of the title, the rule is plain.
+    fn format_section(&self, title: &str) -> String {
+        let (start, end, width) = if self.borders {
+            let [left, _, right] = self.glyphs.box_chars.middle;
+            (left, Some(right), self.inner_width())
+        } else {
+            (
+                self.glyphs.box_chars.horizontal,
+                None,
+                self.total_width().saturating_sub(1),
+            )
+        };
+
+        let horizontal = self.glyphs.box_chars.horizontal;
+        let mut line = String::from(start);
+        // `─ T ─` is the narrowest rule with a title.
+        if width < 5 {
+            line.extend(iter::repeat_n(horizontal, width));
+            line.extend(end);
+            return line;
+        }
+
+        // `─ Title ─`, with at least one trailing `─`.
+        let title = self.truncate(title, width - 4);
+        let fill = width - Table::visible_width(&title) - 3;
+
+        line.push(horizontal);
+        line.push(' ');
+        line.push_str(&title);
+        line.push(' ');
+        line.extend(iter::repeat_n(horizontal, fill));
+        line.extend(end);
+        line
+    }
+
+    /// Horizontal border, with junctions aligned on column separators.
+    fn format_rule(&self, [left, junction, right]: [char; 3]) -> String {
+        let mut line = String::from(left);
+        for (i, width) in self.columns_width.iter().enumerate() {
+            if i > 0 {
+                line.push(junction);
+            }
+            // One space of padding on each side of the cell.
+            let (padding_left, padding_right) = self.cell_padding;
+            let width = width + 2 + padding_left + padding_right;
+            line.extend(iter::repeat_n(self.glyphs.box_chars.horizontal, width));
+        }
+        line.push(right);
+        line
+    }
+
+    /// Horizontal rule between groups of rows.
+    ///
+    /// With borders, it is an interior border. Otherwise, each column
+    /// is underlined, and the column separators are kept.
+    fn format_group_rule(&self) -> String {
+        if self.borders {
+            return self.format_rule(self.glyphs.box_chars.middle);
+        }
+        let (padding_left, padding_right) = self.cell_padding;
+        let mut line = String::with_capacity(self.total_width());
+        for (i, width) in self.columns_width.iter().enumerate() {
+            if i > 0 {
+                line.push_str(self.column_separator(i - 1));
+            }
+            let width = width + padding_left + padding_right;
+            line.extend(iter::repeat_n(self.glyphs.box_chars.horizontal, width));
+        }
+        line
+    }
+
+    /// Separator of the gap following the `gap`-th column.
+    fn column_separator(&self, gap: usize) -> &str {
+        if self.borders {
+            self.glyphs.bordered_column_separator
+        } else if let Some(separators) = self.column_separators {
+            separators[gap]
+        } else {
+            self.column_separator
+        }
+    }
+
+    /// Write a line of the table, margin and line break included.
+    fn write_line(&self, output: &mut Output, line: &str) -> fmt::Result {
+        let line = if self.flags.contains(Flags::STRIP_COLORS) {
+            Table::strip_ansi_colors(line)
+        } else {
+            Cow::Borrowed(line)
+        };
+        if output.is_exhausted {
+            return Ok(());
+        }
+        self.write_raw_line(output, &line)?;
+
+        if output
+            .max_bytes
+            .is_some_and(|max_bytes| output.nb_bytes > max_bytes)
+        {
+            output.is_exhausted = true;
+            self.write_raw_line(output, self.glyphs.output_truncation_marker)?;
+        }
+        Ok(())
+    }
+
+    /// Write a line with its margin, ending the previous line.
+    ///
+    /// Line breaks are delayed until the next line, for the last one to
+    /// be optional.
+    fn write_raw_line(&self, output: &mut Output, line: &str) -> fmt::Result {
+        let line_ending = self.line_ending.as_str();
+        if output.has_pending_newline {
+            output.inner.write_str(line_ending)?;
+        }
+        let line = format!("{:margin$}{line}", "", margin = self.margin_left);
+        output.inner.write_str(&line)?;
+        output.nb_bytes += line.len() + line_ending.len();
+        output.has_pending_newline = true;
+        Ok(())
+    }
+
+    /// Style of the n-th styled row, if zebra-striping is enabled.
+    fn zebra_style(&self, n: usize) -> Option<&str> {
+        self.zebra
+            .map(|(even, odd)| if n.is_multiple_of(2) { even } else { odd })
+    }
+
+    /// Total width of the table, including separators and borders.
+    fn total_width(&self) -> usize {
+        let nb_separators = self.columns_width.len().saturating_sub(1);
+        let separators_width: usize = (0..nb_separators)
+            .map(|gap| Table::visible_width(self.column_separator(gap)))
+            .sum();
+        let (padding_left, padding_right) = self.cell_padding;
+        let padding_width = (padding_left + padding_right) * self.columns_width.len();
+        let width = self.columns_width.iter().sum::<usize>() + padding_width + separators_width;
+        if self.borders {
+            width + 4
+        } else {
+            width
+        }
+    }
+
+    /// Estimated size of the rendered table in bytes, to preallocate
+    /// buffers.
+    ///
+    /// This counts one byte per visible character, so it is exact for
+    /// plain ASCII tables, and a lower bound otherwise.
+    fn estimated_len(&self) -> usize {
+        // Headers, and up to three rules with borders.
+        let nb_lines = self.rows.len() + 4;
+        (self.margin_left + self.total_width() + 1) * nb_lines
+    }
+
+    /// Width of the table between its left and right borders.
+    fn inner_width(&self) -> usize {
+        self.total_width().saturating_sub(2)
+    }
+}
+
+impl fmt::Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f)
+    }
+}
+
+/// Saved state of a [`Table`], see the `Serialize` implementation.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SavedTable<'a> {
+    headers: Option<Vec<Cow<'a, str>>>,
+    alignments: Option<Vec<SavedAlignment>>,
+    max_rows: Option<usize>,
+    column_separator: Option<Cow<'a, str>>,
+    data: Option<Vec<Vec<Cow<'a, str>>>>,
+}
+
+/// Serializable stand-in for `fmt::Alignment`.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SavedAlignment {
+    Left,
+    Right,
+    Center,
+}
+
+#[cfg(feature = "serde")]
+impl From<fmt::Alignment> for SavedAlignment {
+    fn from(alignment: fmt::Alignment) -> Self {
+        match alignment {
+            fmt::Alignment::Left => Self::Left,
+            fmt::Alignment::Right => Self::Right,
+            fmt::Alignment::Center => Self::Center,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SavedAlignment> for fmt::Alignment {
+    fn from(alignment: SavedAlignment) -> Self {
+        match alignment {
+            SavedAlignment::Left => Self::Left,
+            SavedAlignment::Right => Self::Right,
+            SavedAlignment::Center => Self::Center,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Table<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn borrow<'b>(cells: &'b [Cow<str>]) -> Vec<Cow<'b, str>> {
+            cells
+                .iter()
+                .map(|cell| Cow::Borrowed(cell.as_ref()))
+                .collect()
+        }
+
+        SavedTable {
+            headers: self.headers.as_deref().map(borrow),
+            alignments: self
+                .alignments
+                .as_deref()
+                .map(|alignments| alignments.iter().copied().map(Into::into).collect()),
+            max_rows: self.max_rows,
+            column_separator: self.column_separator.as_deref().map(Cow::Borrowed),
+            data: self
+                .data
+                .as_ref()
+                .map(|data| data.iter().map(|row| borrow(row)).collect()),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserialized tables own their data.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Table<'static> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedTable::deserialize(deserializer)?;
+        let mut table = Table::new();
+        table.headers = saved.headers;
+        table.alignments = saved
+            .alignments
+            .map(|alignments| alignments.into_iter().map(Into::into).collect());
+        table.max_rows = saved.max_rows;
+        table.column_separator = saved.column_separator;
+        table.data = saved.data;
+        Ok(table)
+    }
+}
+
+/// Tracks grapheme cluster boundaries, one character at a time.
+///
+/// See [`Table::visible_width()`] for the rules.
+#[derive(Default)]
+struct GraphemeCounter {
+    is_joined: bool,
+    is_flag_open: bool,
+}
+
+impl GraphemeCounter {
+    /// Whether `char` starts a new grapheme cluster.
+    fn starts_cluster(&mut self, char: char) -> bool {
+        if Table::is_grapheme_extender(char) {
+            self.is_joined = char == '\u{200d}';
+            return false;
+        }
+        if self.is_joined {
+            self.is_joined = false;
+            return false;
+        }
+        if ('\u{1f1e6}'..='\u{1f1ff}').contains(&char) {
+            // Regional indicators come in pairs.
+            self.is_flag_open = !self.is_flag_open;
+            return self.is_flag_open;
+        }
+        self.is_flag_open = false;
+        true
+    }
+}
+
+/// Ready-to-render table, created by [`Table::prepare()`].
+///
+/// Checks and conversions are made once, at preparation. A `Prepared`
+/// table can then be rendered any number of times through `Display`,
+/// and be extended with new rows with [`concat()`](Self::concat).
+pub struct Prepared<'a> {
+    source: &'a Table<'a>,
+    table: TableBlueprint<'a>,
+    /// Number of columns of the data, before selection.
+    nb_columns: usize,
+    /// Number of data rows, concatenated ones included.
+    nb_data_rows: usize,
+    /// Widths of the columns before width limits.
+    content_widths: Vec<usize>,
+    split_widths: Vec<SplitWidths>,
+    fixed_widths: Option<Vec<Option<usize>>>,
+}
+
+impl<'a> Prepared<'a> {
+    /// Append rows to the prepared table.
+    ///
+    /// Rows go through the same conversions as the rows of the table,
+    /// and the same width limits apply. Column widths are updated
+    /// incrementally: a column only widens if a new value is wider than
+    /// its current width, then the width limits are applied again.
+    /// Cells already truncated by the limits stay truncated. Rows are
+    /// appended after any elision marker, they are not subject to
+    /// `max_rows`.
+    ///
+    /// Values in columns lined up with [`Table::decimal_align()`] or
+    /// [`Table::align_on()`] line up with the rows before them, unless
+    /// they are wider, in which case only the rows after them are
+    /// widened.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of cells in a row does not match
+    /// the number of columns, or if the table is transposed. No row is
+    /// appended then.
+    pub fn concat(&mut self, rows: &[Vec<&'a str>]) -> Result<&mut Self, TableError> {
+        let source = self.source;
+        if source.flags.contains(Flags::TRANSPOSE) {
+            return Err(TableError::ConcatTransposed);
+        }
+
+        let mut data: Vec<Cells<'a>> = rows
+            .iter()
+            .map(|row| Cells::Owned(row.iter().map(|cell| Cow::Borrowed(*cell)).collect()))
+            .collect();
+        source.clean_rows(&mut data, self.nb_columns);
+        if !data.iter().all(|row| row.len() == self.nb_columns) {
+            return Err(TableError::ColumnsMismatch);
+        }
+
+        source.format_rows(&mut data);
+        source.align_columns(&mut data, &mut self.split_widths);
+        for row in &mut data {
+            source.arrange_row(row);
+        }
+        if source.flags.contains(Flags::ROW_NUMBERS) {
+            Table::number_rows(&mut data, self.nb_data_rows + 1);
+        }
+        if let Some(max_columns) = source.max_columns {
+            for row in &mut data {
+                Table::apply_max_columns_to_row(
+                    row,
+                    max_columns,
+                    source.glyphs().truncation_marker,
+                );
+            }
+        }
+        if source.flags.contains(Flags::ESCAPE_SEPARATOR_COLLISIONS) {
+            source.escape_collisions(&mut [], &mut data);
+        }
+
+        let table = &mut self.table;
+        let group_boundaries = source.group_boundaries.unwrap_or_default();
+        for row in data {
+            if self.nb_data_rows > 0 && group_boundaries.contains(&self.nb_data_rows) {
+                table.rows.push(Row::Rule);
+            }
+            for (width, cell) in self.content_widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(Table::cell_width(cell));
+            }
+            table.rows.push(Row::Cells(row, self.nb_data_rows));
+            self.nb_data_rows += 1;
+        }
+        table.columns_width.clone_from(&self.content_widths);
+        table.pad_last_column = source.borders;
+        source.apply_width_limits(table, self.fixed_widths.as_deref());
+
+        Ok(self)
+    }
+}
+
+impl fmt::Display for Prepared<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.table.render(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_digits_from_the_right() {
+        assert_eq!(group_digits("1234", ','), "1,234");
+        assert_eq!(group_digits("1234567", ','), "1,234,567");
+        assert_eq!(group_digits("1234567890", ','), "1,234,567,890");
+    }
+
+    #[test]
+    fn group_digits_short_numbers_are_borrowed() {
+        assert!(matches!(group_digits("0", ','), Cow::Borrowed("0")));
+        assert!(matches!(group_digits("123", ','), Cow::Borrowed("123")));
+        assert!(matches!(group_digits("-123.4567", ','), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn group_digits_fractional_part_is_not_grouped() {
+        assert_eq!(group_digits("1234567.891011", ','), "1,234,567.891011");
+        assert_eq!(group_digits("1234.", ','), "1,234.");
+    }
+
+    #[test]
+    fn group_digits_keeps_sign() {
+        assert_eq!(group_digits("-1234567", ','), "-1,234,567");
+        assert_eq!(group_digits("+1234", '_'), "+1_234");
+    }
+
+    #[test]
+    fn group_digits_non_numbers_are_left_as_is() {
+        assert_eq!(group_digits("", ','), "");
+        assert_eq!(group_digits("-", ','), "-");
+        assert_eq!(group_digits(".1234", ','), ".1234");
+        assert_eq!(group_digits("1234abc", ','), "1234abc");
+        assert_eq!(group_digits("12 345", ','), "12 345");
+        assert_eq!(group_digits("1.2.3456", ','), "1.2.3456");
+    }
+
+    #[test]
+    fn table_default_builder() {
+        assert_eq!(Table::new(), Table::default());
+    }
+
+    #[test]
+    fn table_regular() {
+        let table = Table::new()
+            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+            ])
+            .data(&[
+                vec![
+                    "Value larger than header",
+                    "Column name has space",
+                    "No trailing whitespace",
+                ],
+                vec!["---", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+SHORT                     WITH SPACE             LAST COLUMN
+Value larger than header  Column name has space  No trailing whitespace
+---                       ---                    ---
+"
+        );
+    }
+
+    #[test]
+    fn table_single_column() {
+        let table = Table::new()
+            .headers(&["foo"])
+            .data(&[vec!["bar"], vec!["baz"]])
+            .column_separator("|")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+foo
+bar
+baz
+"
+        );
+    }
+
+    #[test]
+    fn table_without_data() {
+        let table = Table::new()
+            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+SHORT  WITH SPACE  LAST COLUMN
+"
+        );
+    }
+
+    #[test]
+    fn table_all_empty_headers_not_rendered() {
+        let table = Table::new()
+            .headers(&["", ""])
+            .data(&[vec!["---", "----------------"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+---  ----------------
+"
+        );
+    }
+
+    #[test]
+    fn table_all_empty_headers_not_rendered_with_borders() {
+        let mut table = Table::new();
+        table
+            .headers(&["", ""])
+            .push_row(["---", "-----"])
+            .borders(true);
+
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+┌─────┬───────┐
+│ --- │ ----- │
+└─────┴───────┘
+"
+        );
+
+        table.clear_data();
+        assert_eq!(table.to_string(), "┌──┬──┐\n└──┴──┘\n");
+    }
+
+    #[test]
+    fn table_some_empty_headers_all_rendered() {
+        let table = Table::new()
+            .headers(&["", "-"])
+            .data(&[vec!["---", "----------------"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            r"     -
+---  ----------------
+"
+        );
+    }
+
+    #[test]
+    fn table_default_headers() {
+        let table = Table::new()
+            .data(&[vec!["---", "----------------"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+---  ----------------
+"
+        );
+    }
+
+    #[test]
+    fn table_headers_alignment() {
+        let table = Table::new()
+            .headers(&["ALIGN-LEFT", "ALIGN-CENTER", "ALIGN-RIGHT"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Center,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec![
+                    "Header is aligned Left",
+                    "Header is aligned Center",
+                    "Header is aligned Right",
+                ],
+                vec!["---", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+ALIGN-LEFT                    ALIGN-CENTER                    ALIGN-RIGHT
+Header is aligned Left  Header is aligned Center  Header is aligned Right
+---                               ---                                 ---
+"
+        );
+    }
+
+    #[test]
+    fn table_values_alignment() {
+        let table = Table::new()
+            .headers(&["ALIGN-LEFT", "ALIGN-CENTER", "ALIGN-RIGHT"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Center,
+                fmt::Alignment::Right,
+            ])
+            .data(&[vec!["Left", "Center", "Right"], vec!["---", "---", "---"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+ALIGN-LEFT  ALIGN-CENTER  ALIGN-RIGHT
+Left           Center           Right
+---             ---               ---
+"
+        );
+    }
+
+    #[test]
+    fn table_default_alignments() {
+        let table = Table::new()
+            .headers(&["VALUE LEFT", "COLUMN LEFT"])
+            .data(&[vec!["---", "----------------"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+VALUE LEFT  COLUMN LEFT
+---         ----------------
+"
+        );
+    }
+
+    #[test]
+    fn table_default_headers_same_geometry_as_empty_headers() {
+        let data = [vec!["a", "1.5"], vec!["bbb", "22"], vec!["c", "3"]];
+        let mut without_headers = Table::new();
+        without_headers
+            .data(&data)
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right]);
+        let mut with_empty_headers = without_headers.clone();
+        with_empty_headers.headers(&["", ""]);
+
+        assert_eq!(
+            without_headers.to_string(),
+            "a    1.5\nbbb   22\nc      3\n"
+        );
+        assert_eq!(
+            without_headers.column_widths(),
+            with_empty_headers.column_widths()
+        );
+        assert_eq!(without_headers.to_string(), with_empty_headers.to_string());
+
+        without_headers
+            .borders(true)
+            .row_numbers(true)
+            .max_width(12);
+        with_empty_headers
+            .borders(true)
+            .row_numbers(true)
+            .max_width(12);
+        assert_eq!(without_headers.to_string(), with_empty_headers.to_string());
+
+        without_headers.borders(false).max_rows(1).transpose(true);
+        with_empty_headers
+            .borders(false)
+            .max_rows(1)
+            .transpose(true);
+        assert_eq!(without_headers.to_string(), with_empty_headers.to_string());
+    }
+
+    #[test]
+    fn table_default_headers_and_alignments() {
+        let table = Table::new()
+            .data(&[
+                vec!["---", "----------------"],
+                vec!["----------------", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+---               ----------------
+----------------  ---
+"
+        );
+    }
+
+    #[test]
+    fn table_with_empty_data() {
+        let table = Table::new()
+            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+            ])
+            .data(&[] as &[Vec<&str>; 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+SHORT  WITH SPACE  LAST COLUMN
+"
+        );
+    }
+
+    #[test]
+    fn table_with_empty_data_and_column_separator() {
+        let table = Table::new()
+            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
+            .data(&[] as &[Vec<&str>; 0])
+            .column_separator("|")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "SHORT|WITH SPACE|LAST COLUMN\n");
+    }
+
+    #[test]
+    fn table_header_only_has_same_geometry_as_populated() {
+        let mut table = Table::new();
+        table
+            .headers(&["\x1b[1mNAME\x1b[0m", "SIZE", "TAGS"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Center,
+                fmt::Alignment::Right,
+            ])
+            .column_separator("|")
+            .max_width(20)
+            .stretch_last_column(true);
+
+        let header_only = table.to_string();
+        let populated = table.push_row(["a", "1", "b"]).to_string();
+
+        println!("{header_only}");
+        println!("{populated}");
+        assert_eq!(header_only, "\x1b[1mNAME\x1b[0m|SIZE|      TAGS\n");
+        assert_eq!(header_only.lines().next(), populated.lines().next());
+    }
+
+    #[test]
+    fn table_completely_empty() {
+        let table = Table::new()
+            .headers(&[] as &[&str; 0])
+            .alignments(&[])
+            .data(&[] as &[Vec<&str>; 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "headers and data cannot both be empty")]
+    fn table_error_completely_empty_with_default_headers() {
+        let table = Table::new()
+            .alignments(&[])
+            .data(&[] as &[Vec<&str>; 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "\n");
+    }
+
+    #[test]
+    fn table_completely_empty_with_default_alignments() {
+        let table = Table::new()
+            .headers(&[] as &[&str; 0])
+            .data(&[] as &[Vec<&str>; 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "headers and data cannot both be empty")]
+    fn table_error_completely_empty_with_default_headers_and_alignments() {
+        let table = Table::new().data(&[] as &[Vec<&str>; 0]).to_string();
+
+        println!("{table}");
+        assert_eq!(table, "\n");
+    }
+
+    #[test]
+    fn table_alignments_shorter_than_columns() {
+        let table = Table::new()
+            .headers(&["A", "B", "C"])
+            .alignments(&[fmt::Alignment::Right])
+            .push_row(["aaa", "bbb", "ccc"])
+            .push_row(["a", "b", "c"])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "  A  B    C
+aaa  bbb  ccc
+  a  b    c
+"
+        );
+    }
+
+    #[test]
+    fn table_alignments_from_str() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B", "C"])
+            .push_row(["aaa", "bbb", "ccc"]);
+        let mut expected = table.clone();
+
+        table.alignments_from_str(&["left", "R", "Center"]).unwrap();
+        expected.alignments(&[
+            fmt::Alignment::Left,
+            fmt::Alignment::Right,
+            fmt::Alignment::Center,
+        ]);
+
+        assert_eq!(table.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn table_alignments_from_str_invalid() {
+        let mut table = Table::new();
+        let error = table.alignments_from_str(&["l", "middle"]).unwrap_err();
+
+        assert_eq!(error, TableError::InvalidAlignment { index: 1 });
+        assert_eq!(error.to_string(), "alignment 1 is not recognized");
+        assert_eq!(table, Table::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "number of headers must match alignments")]
+    fn table_error_nb_headers_neq_nb_alignments() {
+        Table::new()
+            .headers(&["COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+            ])
+            .data(&[vec!["---", "---"]])
+            .to_string();
+    }
+
+    #[test]
+    fn table_pad_short_rows() {
+        let table = Table::new()
+            .headers(&["A", "B", "C"])
+            .alignments(&[fmt::Alignment::Right; 3])
+            .push_row(["a1", "b1"])
+            .push_row(["a2", "b2", "c2"])
+            .push_row(["a3"])
+            .pad_short_rows(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            " A   B   C
+a1  b1    \n\
+a2  b2  c2
+a3        \n\
+"
+        );
+    }
+
+    #[test]
+    fn table_pad_short_rows_without_headers() {
+        let table = Table::new()
+            .push_row(["a1"])
+            .push_row(["a2", "b2", "c2"])
+            .pad_short_rows(true)
+            .to_string();
+
+        assert_eq!(table, "a1      \na2  b2  c2\n");
+    }
+
+    #[test]
+    fn table_truncate_long_rows() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B"])
+            .push_row(["a1", "b1", "c1"])
+            .push_row(["a2"]);
+
+        let error = table.try_render(&mut String::new());
+        assert_eq!(error, Err(TableError::ColumnsMismatch));
+
+        table.pad_short_rows(true).truncate_long_rows(true);
+        assert_eq!(table.to_string(), "A   B\na1  b1\na2  \n");
+    }
+
+    #[test]
+    #[should_panic(expected = "number of headers must match columns in data")]
+    fn table_error_nb_headers_neq_nb_columns_in_data() {
+        Table::new()
+            .headers(&["COLUMN 1", "COLUMN 2"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Left])
+            .data(&[
+                vec!["---", "---"],
+                vec!["---", "---", "---"],
+                vec!["---", "---"],
+            ])
+            .to_string();
+    }
+
+    #[test]
+    fn table_try_render() {
+        let mut output = String::new();
+        Table::new()
+            .headers(&["A", "B"])
+            .push_row(["1", "2"])
+            .try_render(&mut output)
+            .unwrap();
+
+        assert_eq!(output, "A  B\n1  2\n");
+    }
+
+    #[test]
+    fn table_try_render_errors() {
+        let mut output = String::new();
+
+        let error = Table::new().try_render(&mut output);
+        assert_eq!(error, Err(TableError::Empty));
+
+        let error = Table::new()
+            .headers(&["A"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Left])
+            .try_render(&mut output);
+        assert_eq!(error, Err(TableError::AlignmentsMismatch));
+
+        let error = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["1"])
+            .try_render(&mut output);
+        assert_eq!(error, Err(TableError::ColumnsMismatch));
+
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn table_columns_select_and_reorder() {
+        let table = Table::new()
+            .headers(&["A", "BB", "CCC"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .push_row(["a1", "b1", "c1"])
+            .push_row(["a2", "b2", "c2"])
+            .columns(&[2, 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+CCC  A
+ c1  a1
+ c2  a2
+"
+        );
+    }
+
+    #[test]
+    fn table_reverse_columns() {
+        let table = Table::new()
+            .headers(&["FIRST", "B", "C"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+                fmt::Alignment::Left,
+            ])
+            .push_row(["a", "b", "long c"])
+            .reverse_columns(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+C       B  FIRST
+long c  b  a
+"
+        );
+    }
+
+    #[test]
+    fn table_columns_repeated_index_duplicates_column() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["1", "2"])
+            .columns(&[1, 1])
+            .to_string();
+
+        assert_eq!(table, "B  B\n2  2\n");
+    }
+
+    #[test]
+    fn table_columns_out_of_range() {
+        let mut output = String::new();
+        let error = Table::new()
+            .headers(&["A", "B"])
+            .columns(&[0, 2])
+            .try_render(&mut output);
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 2,
+                nb_columns: 2
+            })
+        );
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "column 2 out of range for 2 columns"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 out of range for 2 columns")]
+    fn table_error_columns_out_of_range() {
+        Table::new().headers(&["A", "B"]).columns(&[3]).to_string();
+    }
+
+    #[test]
+    fn table_sort_by_column_numeric() {
+        let table = Table::new()
+            .headers(&["COMMAND", "PID"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .data(&[
+                vec!["rapportd", "449"],
+                vec!["Python", "22396"],
+                vec!["foo", "108"],
+                vec!["rustrover", "\x1b[1m30928\x1b[0m"],
+                vec!["bar", "108"],
+                vec!["Transmiss", "94671"],
+            ])
+            .sort_by_column(1, SortOrder::Asc)
+            .max_rows(4)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+COMMAND      PID
+foo          108
+bar          108
+...          ...
+rustrover  \x1b[1m30928\x1b[0m
+Transmiss  94671
+"
+        );
+    }
+
+    #[test]
+    fn table_sort_by_column_lexicographic_desc() {
+        let table = Table::new()
+            .headers(&["NAME", "N"])
+            .data(&[
+                vec!["b", "1"],
+                vec!["10", "2"],
+                vec!["a", "3"],
+                vec!["9", "4"],
+                vec!["b", "5"],
+                vec!["Nan", "6"],
+                vec!["Infinity", "7"],
+            ])
+            .sort_by_column(0, SortOrder::Desc)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+NAME      N
+b         1
+b         5
+a         3
+Nan       6
+Infinity  7
+10        2
+9         4
+"
+        );
+    }
+
+    #[test]
+    fn table_sort_by_column_out_of_range() {
+        let error = Table::new()
+            .headers(&["A"])
+            .sort_by_column(1, SortOrder::Asc)
+            .try_render(&mut String::new());
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 1,
+                nb_columns: 1
+            })
+        );
+    }
+
+    #[test]
+    fn table_summary_if_over() {
+        let data: Vec<Vec<String>> = (0..1000)
+            .map(|i| vec![i.to_string(), "---".to_string()])
+            .collect();
+
+        let table = Table::new()
+            .headers(&["#", "DATA"])
+            .data(&data)
+            .legend(&[("#", "Row number")])
+            .margin_left(2)
+            .summary_if_over(100, "{rows} rows × {cols} columns (too large to display)")
+            .to_string();
+
+        assert_eq!(table, "  1,000 rows × 2 columns (too large to display)\n");
+    }
+
+    #[test]
+    fn table_summary_if_over_counts_user_columns() {
+        let table = Table::new()
+            .headers(&["A", "B", "C"])
+            .push_row(["1", "2", "3"])
+            .push_row(["4", "5", "6"])
+            .columns(&[0, 2])
+            .row_numbers(true)
+            .summary_if_over(1, "{rows} rows × {cols} cols")
+            .to_string();
+
+        assert_eq!(table, "2 rows × 2 cols\n");
+    }
+
+    #[test]
+    fn table_summary_if_over_under_threshold() {
+        let table = Table::new()
+            .headers(&["#", "DATA"])
+            .push_row(["1", "---"])
+            .push_row(["2", "---"])
+            .summary_if_over(2, "{rows} rows")
+            .to_string();
+
+        assert_eq!(table, "#  DATA\n1  ---\n2  ---\n");
+    }
+
+    #[test]
+    fn table_decimal_align() {
+        let table = Table::new()
+            .headers(&["AMOUNT", "CURRENCY"])
+            .alignments(&[fmt::Alignment::Right, fmt::Alignment::Left])
+            .push_row(["1,234.5", "EUR"])
+            .push_row(["9.75", "USD"])
+            .push_row(["100", "CHF"])
+            .decimal_align(&[0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "  AMOUNT  CURRENCY
+1,234.5   EUR
+    9.75  USD
+  100     CHF
+"
+        );
+    }
+
+    #[test]
+    fn table_decimal_align_mixed_with_text() {
+        let table = Table::new()
+            .headers(&["VALUE", "LABEL"])
+            .alignments(&[fmt::Alignment::Right, fmt::Alignment::Left])
+            .push_row(["1.5", "-"])
+            .push_row(["N/A", "-"])
+            .push_row(["\x1b[92m10.25\x1b[0m", "-"])
+            .push_row(["-3", "-"])
+            .decimal_align(&[0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+VALUE  LABEL
+ 1.5   -
+  N/A  -
+\x1b[92m10.25\x1b[0m  -
+-3     -
+"
+        );
+    }
+
+    #[test]
+    fn table_decimal_align_text_wider_than_numbers() {
+        let table = Table::new()
+            .headers(&["N"])
+            .alignments(&[fmt::Alignment::Center])
+            .push_row(["1.5"])
+            .push_row(["unknown"])
+            .push_row(["22"])
+            .decimal_align(&[0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "   N   \n  1.5  \nunknown\n 22    \n");
+    }
+
+    #[test]
+    fn table_decimal_align_out_of_range() {
+        let error = Table::new()
+            .headers(&["A"])
+            .decimal_align(&[1])
+            .try_render(&mut String::new());
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 1,
+                nb_columns: 1
+            })
+        );
+    }
+
+    #[test]
+    fn table_align_on() {
+        let table = Table::new()
+            .headers(&["COMMAND", "HOST:PORTS"])
+            .push_row(["rapportd", "*:8000"])
+            .push_row(["rustrover", "127.0.0.1:63342"])
+            .push_row(["Transmiss", "*:51413"])
+            .push_row(["foo", "-"])
+            .align_on(1, ':')
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+COMMAND    HOST:PORTS
+rapportd           *:8000 \n\
+rustrover  127.0.0.1:63342
+Transmiss          *:51413
+foo                -      \n\
+"
+        );
+    }
+
+    #[test]
+    fn table_align_on_colored_cells() {
+        for separator in [';', ':'] {
+            let colored = format!("\x1b[38{separator}5{separator}1mab{separator}c\x1b[0m");
+            let plain = format!("x{separator}yy");
+            let table = Table::new()
+                .headers(&["A"])
+                .push_row([colored.as_str()])
+                .push_row([plain.as_str()])
+                .align_on(0, separator)
+                .to_string();
+
+            println!("{table}");
+            let positions: Vec<Option<usize>> = Table::strip_ansi_colors(&table)
+                .lines()
+                .skip(1)
+                .map(|line| line.find(separator))
+                .collect();
+            assert_eq!(positions, [Some(2), Some(2)], "separator = {separator:?}");
+        }
+    }
+
+    #[test]
+    fn table_align_on_out_of_range() {
+        let error = Table::new()
+            .headers(&["A"])
+            .align_on(1, ':')
+            .try_render(&mut String::new());
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 1,
+                nb_columns: 1
+            })
+        );
+    }
+
+    #[test]
+    fn table_group_numeric() {
+        let table = Table::new()
+            .headers(&["NAME", "BYTES"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["a", "1234567"])
+            .push_row(["b", "abc"])
+            .push_row(["c", "-98765.4321"])
+            .push_row(["d", "999"])
+            .group_numeric(&[1], ',')
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+NAME         BYTES
+a        1,234,567
+b              abc
+c     -98,765.4321
+d              999
+"
+        );
+    }
+
+    #[test]
+    fn table_group_numeric_out_of_range() {
+        let error = Table::new()
+            .headers(&["A"])
+            .group_numeric(&[1], ',')
+            .try_render(&mut String::new());
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 1,
+                nb_columns: 1
+            })
+        );
+    }
+
+    #[test]
+    fn table_bar_column() {
+        let table = Table::new()
+            .headers(&["DISK", "USED"])
+            .push_row(["/", "50"])
+            .push_row(["/home", "100"])
+            .push_row(["/tmp", "12.5"])
+            .push_row(["/mnt", "n/a"])
+            .push_row(["/var", "-3"])
+            .bar_column(1, 100.0, 10)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+DISK   USED
+/      █████░░░░░
+/home  ██████████
+/tmp   █░░░░░░░░░
+/mnt   \n\
+/var   ░░░░░░░░░░
+"
+        );
+    }
+
+    #[test]
+    fn table_bar_column_non_finite_values() {
+        let table = Table::new()
+            .headers(&["V"])
+            .push_row(["inf"])
+            .push_row(["NaN"])
+            .push_row(["-inf"])
+            .push_row(["1"])
+            .bar_column(0, 2.0, 4)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "V\n\n\n\n██░░\n");
+    }
+
+    #[test]
+    fn table_bar_column_non_positive_max() {
+        for max in [0.0, -1.0, f64::NAN] {
+            let table = Table::new()
+                .headers(&["V"])
+                .push_row(["0"])
+                .push_row(["5"])
+                .bar_column(0, max, 3)
+                .to_string();
+
+            assert_eq!(table, "V\n░░░\n░░░\n", "max = {max}");
+        }
+    }
+
+    #[test]
+    fn table_bar_column_out_of_range() {
+        let error = Table::new()
+            .headers(&["A"])
+            .bar_column(1, 1.0, 1)
+            .try_render(&mut String::new());
+
+        assert_eq!(
+            error,
+            Err(TableError::ColumnOutOfRange {
+                index: 1,
+                nb_columns: 1
+            })
+        );
+    }
+
+    #[test]
+    fn table_max_columns() {
+        let table = Table::new()
+            .headers(&["A", "BB", "C", "D", "E", "F"])
+            .alignments(&[fmt::Alignment::Right; 6])
+            .push_row(["1", "2", "3", "4", "5", "6"])
+            .push_row(["11", "22", "33", "44", "55", "66"])
+            .push_row(["111", "222", "333", "444", "555", "666"])
+            .max_columns(3)
+            .max_rows(2)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "  A   BB  …
+  1    2  …
+...  ...  ...
+111  222  …
+"
+        );
+    }
+
+    #[test]
+    fn table_max_columns_zero() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["1", "2"])
+            .max_columns(0)
+            .to_string();
+
+        assert_eq!(table, "…\n…\n");
+    }
+
+    #[test]
+    fn table_max_columns_without_headers() {
+        let table = Table::new()
+            .data(&[vec!["1", "2", "3"]])
+            .max_columns(2)
+            .to_string();
+
+        assert_eq!(table, "1  …\n");
+    }
+
+    #[test]
+    fn table_max_columns_noop() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["1", "2"])
+            .max_columns(2)
+            .to_string();
+
+        assert_eq!(table, "A  B\n1  2\n");
+    }
+
+    #[test]
+    fn table_max_rows_regular() {
+        let table = Table::new()
+            .max_rows(5)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "---", "---"],
+                vec!["3.", "------------", "------------"],
+                vec!["4.", "------------", "------------"],
+                vec!["5.", "---", "---"],
+                vec!["6.", "---", "---"],
+                vec!["7.", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#    COLUMN 1  COLUMN 2
+1.   ---            ---
+2.   ---            ---
+...  ...            ...
+5.   ---            ---
+6.   ---            ---
+7.   ---            ---
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_smallest_regular_case() {
+        let table = Table::new()
+            .max_rows(2)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "---", "---"],
+                vec!["3.", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#    COLUMN 1  COLUMN 2
+1.   ---            ---
+...  ...            ...
+3.   ---            ---
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_elided_rows_do_not_impact_column_width() {
+        let table = Table::new()
+            .max_rows(1)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "------------", "------------"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#    COLUMN 1  COLUMN 2
+1.   ---            ---
+...  ...            ...
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_gt_nb_rows() {
+        let table = Table::new()
+            .max_rows(8)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "---", "---"],
+                vec!["3.", "------------", "------------"],
+                vec!["4.", "------------", "------------"],
+                vec!["5.", "---", "---"],
+                vec!["6.", "---", "---"],
+                vec!["7.", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#   COLUMN 1          COLUMN 2
+1.  ---                    ---
+2.  ---                    ---
+3.  ------------  ------------
+4.  ------------  ------------
+5.  ---                    ---
+6.  ---                    ---
+7.  ---                    ---
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_eq_nb_rows() {
+        let table = Table::new()
+            .max_rows(7)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "---", "---"],
+                vec!["3.", "------------", "------------"],
+                vec!["4.", "------------", "------------"],
+                vec!["5.", "---", "---"],
+                vec!["6.", "---", "---"],
+                vec!["7.", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#   COLUMN 1          COLUMN 2
+1.  ---                    ---
+2.  ---                    ---
+3.  ------------  ------------
+4.  ------------  ------------
+5.  ---                    ---
+6.  ---                    ---
+7.  ---                    ---
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_max_zero() {
+        let table = Table::new()
+            .max_rows(0)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "------------", "------------"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#    COLUMN 1  COLUMN 2
+...  ...            ...
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_max_zero_with_empty_data() {
+        let table = Table::new()
+            .max_rows(0)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[] as &[Vec<&str>; 0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#  COLUMN 1  COLUMN 2
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_max_zero_without_header() {
+        // It is forbidden to have both empty headers and empty data.
+        // Here we render with a 100% valid table, but clear the data
+        // through `max_rows(0)`.
+        let table = Table::new()
+            .max_rows(0)
+            .data(&[vec!["---", "----------------"]])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "...  ...\n");
+    }
+
+    #[test]
+    fn table_max_rows_max_one() {
+        let table = Table::new()
+            .max_rows(1)
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .data(&[
+                vec!["1.", "---", "---"],
+                vec!["2.", "------------", "------------"],
+                vec!["3.", "---", "---"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#    COLUMN 1  COLUMN 2
+1.   ---            ---
+...  ...            ...
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_elision_alignment() {
+        let mut table = Table::new();
+        table
+            .headers(&["NUMBER", "AMOUNT"])
+            .alignments(&[fmt::Alignment::Right; 2])
+            .push_row(["1", "10.00"])
+            .push_row(["2", "20.00"])
+            .push_row(["3", "30.00"])
+            .max_rows(2);
+
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+NUMBER  AMOUNT
+     1   10.00
+   ...     ...
+     3   30.00
+"
+        );
+
+        table.elision_alignment(fmt::Alignment::Left);
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+NUMBER  AMOUNT
+     1   10.00
+...     ...
+     3   30.00
+"
+        );
+    }
+
+    #[test]
+    fn table_max_rows_pin_rows() {
+        let mut table = Table::new();
+        table.headers(&["N"]);
+        for i in 0..10 {
+            table.push_row([i.to_string()]);
+        }
+
+        let pinned = table.max_rows(4).pin_rows(&[0, 9]).to_string();
+        assert_eq!(pinned, "N\n0\n1\n...\n8\n9\n");
+
+        let pinned = table.max_rows(3).pin_rows(&[5]).to_string();
+        assert_eq!(pinned, "N\n0\n...\n5\n...\n9\n");
+
+        let pinned = table.max_rows(1).pin_rows(&[3, 4, 42]).to_string();
+        assert_eq!(pinned, "N\n...\n3\n4\n...\n");
+    }
+
+    #[test]
+    fn table_max_rows_pin_rows_follow_sorted_rows() {
+        let table = Table::new()
+            .headers(&["ITEM", "N"])
+            .push_row(["total", "60"])
+            .push_row(["a", "10"])
+            .push_row(["b", "30"])
+            .push_row(["c", "20"])
+            .sort_by_column(1, SortOrder::Asc)
+            .max_rows(2)
+            .pin_rows(&[0])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "ITEM   N\na      10\n...    ...\ntotal  60\n");
+    }
+
+    #[test]
+    fn table_row_numbers() {
+        let mut table = Table::new();
+        table.headers(&["NAME"]).row_numbers(true).max_rows(5);
+        for name in ["a", "b", "c", "d", "e", "f", "g"] {
+            table.push_row([name]);
+        }
+
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "  #  NAME
+  1  a
+  2  b
+...  ...
+  5  e
+  6  f
+  7  g
+"
+        );
+
+        table.row_numbers_header("N°").max_rows(1);
+        assert_eq!(table.to_string(), " N°  NAME\n  1  a\n...  ...\n");
+    }
+
+    #[test]
+    fn table_row_numbers_without_headers() {
+        let table = Table::new()
+            .push_row(["a"])
+            .push_row(["b"])
+            .row_numbers(true)
+            .to_string();
+
+        assert_eq!(table, "1  a\n2  b\n");
+    }
+
+    #[test]
+    fn table_column_separator() {
+        let table = Table::new()
+            .headers(&["1", "2", "3"])
+            .alignments(&[
+                fmt::Alignment::Right,
+                fmt::Alignment::Center,
+                fmt::Alignment::Left,
+            ])
+            .data(&[
+                vec!["---", "---", "---"],
+                vec!["------", "------", "------"],
+                vec!["---", "---", "---"],
+            ])
+            .column_separator("|")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "     1|  2   |3
+   ---| ---  |---
+------|------|------
+   ---| ---  |---
+"
+        );
+    }
+
+    #[test]
+    fn table_column_separator_owned() {
+        let padding = 1;
+        let table = Table::new()
+            .headers(&["1", "2"])
+            .push_row(["---", "---"])
+            .column_separator_owned(format!("{0}|{0}", " ".repeat(padding)))
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+1   | 2
+--- | ---
+"
+        );
+    }
+
+    #[test]
+    fn table_escape_separator_collisions() {
+        let mut table = Table::new();
+        table
+            .headers(&["A  B", "C"])
+            .push_row(["a  b", "c"])
+            .push_row(["ab", "c"]);
+
+        assert_eq!(table.to_string(), "A  B  C\na  b  c\nab    c\n");
+
+        table.escape_separator_collisions(true);
+        println!("{table}");
+        assert_eq!(table.to_string(), "A\\  B  C\na\\  b  c\nab     c\n");
+
+        table.collision_marker("·");
+        println!("{table}");
+        assert_eq!(table.to_string(), "A·B  C\na·b  c\nab   c\n");
+    }
+
+    #[test]
+    fn table_escape_separator_collisions_with_borders() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a│b"])
+            .borders(true)
+            .escape_separator_collisions(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "┌──────┐\n│ A    │\n├──────┤\n│ a\\│b │\n└──────┘\n");
+    }
+
+    #[test]
+    fn table_column_separators() {
+        let table = Table::new()
+            .headers(&["A", "B", "C", "D"])
+            .push_row(["a", "b", "c", "d"])
+            .column_separators(&[" ", " | ", "\x1b[2m:\x1b[0m"])
+            .push_span_row("end", fmt::Alignment::Right)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+A B | C\x1b[2m:\x1b[0mD
+a b | c\x1b[2m:\x1b[0md
+      end
+"
+        );
+    }
+
+    #[test]
+    fn table_column_separators_ignored_with_borders() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .column_separators(&["|"])
+            .borders(true)
+            .to_string();
+
+        assert_eq!(table, "┌───┬───┐\n│ A │ B │\n└───┴───┘\n");
+    }
+
+    #[test]
+    fn table_column_separators_mismatch() {
+        let error = Table::new()
+            .headers(&["A", "B", "C"])
+            .column_separators(&["|"])
+            .try_render(&mut String::new());
+
+        assert_eq!(error, Err(TableError::SeparatorsMismatch));
+    }
+
+    #[test]
+    fn table_render_multiple_times() {
+        let data = [vec!["---"]];
+        let table = Table::new()
+            .headers(&["HEADER"])
+            .alignments(&[fmt::Alignment::Left])
+            .data(&data)
+            .to_owned();
+
+        let render_1 = table.to_string();
+        let render_2 = table.to_string();
+
+        println!("{table}");
+
+        assert_eq!(render_1, "HEADER\n---\n");
+        assert_eq!(render_1, render_2);
+    }
+
+    #[test]
+    fn table_render_borrows_data() {
+        let data: Vec<Vec<String>> = (0..1000)
+            .map(|i| vec![i.to_string(), "-".repeat(i % 10)])
+            .collect();
+        let mut table = Table::new();
+        table.headers(&["#", "DATA"]).data(&data);
+
+        let is_borrowed = |table: &Table| {
+            let blueprint = table.make_table_blueprint();
+            blueprint
+                .rows
+                .iter()
+                .all(|row| matches!(row, Row::Cells(Cells::Borrowed(_), _)))
+        };
+
+        assert!(is_borrowed(&table));
+        assert!(is_borrowed(&table));
+        assert_eq!(table.to_string(), table.to_string());
+
+        // Rows are only cloned if a cell is modified.
+        table.max_cell_width(9);
+        assert!(is_borrowed(&table));
+        table.reverse_columns(true);
+        assert!(!is_borrowed(&table));
+    }
+
+    #[test]
+    fn table_fixed_column_widths() {
+        let table = Table::new()
+            .headers(&["NAME", "CODE", "N"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .push_row(["a", "ab", "1"])
+            .push_row(["b", "abcdefg", "22"])
+            .push_row(["c", "\x1b[1mabcdefg\x1b[0m", "333"])
+            .fixed_column_widths(&[None, Some(4)])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+NAME  CODE    N
+a     ab      1
+b     abc…   22
+c     \x1b[1mabc…\x1b[0m  333
+"
+        );
+    }
+
+    #[test]
+    fn table_fixed_column_widths_multi_line_cells() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["ab\ncd"])
+            .push_row(["abc\nd"])
+            .fixed_column_widths(&[Some(2)])
+            .to_string();
+
+        assert_eq!(table, "A\nab\ncd\na…\nd\n");
+    }
+
+    #[test]
+    fn table_fixed_column_widths_follow_columns() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["aaa", "bbb"])
+            .fixed_column_widths(&[Some(2), Some(5)])
+            .columns(&[1, 0])
+            .to_string();
+
+        assert_eq!(table, "B      A\nbbb    a…\n");
+    }
+
+    #[test]
+    fn table_widths_cache() {
+        let mut table = Table::new();
+        table.headers(&["A", "B"]).push_row(["a", "b"]);
+        assert!(table.columns_width.0.get().is_none());
+
+        assert_eq!(table.to_string(), "A  B\na  b\n");
+        assert_eq!(table.columns_width.0.get(), Some(&vec![1, 1]));
+        assert_eq!(table.to_string(), "A  B\na  b\n");
+
+        table.push_row(["ccc", "d"]);
+        assert!(table.columns_width.0.get().is_none());
+        assert_eq!(table.to_string(), "A    B\na    b\nccc  d\n");
+        assert_eq!(table.columns_width.0.get(), Some(&vec![3, 1]));
+
+        table.max_rows(0);
+        assert_eq!(table.to_string(), "A    B\n...  ...\n");
+
+        // Clones share the widths, but compare equal either way.
+        let clone = table.clone();
+        table.columns_width.invalidate();
+        assert_eq!(clone.columns_width.0.get(), Some(&vec![3, 3]));
+        assert_eq!(clone, table);
+    }
+
+    #[test]
+    fn table_render_into() {
+        let mut table = Table::new();
+        table.headers(&["A", "B"]).push_row(["a", "b"]);
+
+        let mut buf = String::new();
+        table.render_into(&mut buf);
+        assert_eq!(buf, table.to_string());
+
+        let mut report = String::from("# Report\n");
+        table.render_into(&mut report);
+        table.push_row(["c", "d"]).render_into(&mut report);
+        assert_eq!(report, "# Report\nA  B\na  b\nA  B\na  b\nc  d\n");
+    }
+
+    #[test]
+    fn table_clear_data_reuse_builder() {
+        let data_1 = [vec!["----------"], vec!["---"]];
+        let data_2 = [vec!["--"]];
+
+        let mut table = Table::new();
+        table
+            .headers(&["HEADER"])
+            .alignments(&[fmt::Alignment::Right])
+            .data(&data_1);
+
+        assert_eq!(table.to_string(), "    HEADER\n----------\n       ---\n");
+
+        table.clear_data();
+        assert_eq!(table.to_string(), "HEADER\n");
+
+        table.data(&data_2);
+        assert_eq!(table.to_string(), "HEADER\n    --\n");
+    }
+
+    #[test]
+    fn table_clear() {
+        let data = [vec!["---"]];
+        let mut table = Table::new();
+        table
+            .headers(&["HEADER"])
+            .data(&data)
+            .max_rows(1)
+            .column_separator("|");
+
+        table.clear();
+
+        assert_eq!(table, Table::new());
+    }
+
+    #[test]
+    fn table_push_row() {
+        let table = Table::new()
+            .headers(&["BORROWED", "OWNED"])
+            .push_row(["---", "---"])
+            .push_row(vec![String::from("------"), format!("{}", 42)])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+BORROWED  OWNED
+---       ---
+------    42
+"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_from_json_arrays() {
+        let json = serde_json::json!([["NAME", "SIZE"], ["foo", 42], ["bar", null, true]]);
+
+        let table = Table::from_json(&json, true).unwrap().to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+NAME  SIZE  \n\
+foo   42    \n\
+bar         true\n\
+"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_from_json_arrays_without_headers() {
+        let json = serde_json::json!([["foo", 4.2], ["bar", false]]);
+
+        let table = Table::from_json(&json, false).unwrap().to_string();
+
+        println!("{table}");
+        assert_eq!(table, "foo  4.2\nbar  false\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_from_json_objects_with_extra_keys() {
+        let json = serde_json::json!([
+            {"name": "foo", "size": 42},
+            {"size": 7, "name": "bar", "tags": ["a", "b"]},
+        ]);
+
+        let table = Table::from_json(&json, false).unwrap().to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+name  size  tags
+foo   42    \n\
+bar   7     [\"a\",\"b\"]
+"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_from_json_outlives_json() {
+        let table = {
+            let json = serde_json::json!([{"A": "a"}]);
+            Table::from_json(&json, false).unwrap()
+        };
+
+        assert_eq!(table.to_string(), "A\na\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_serde_round_trip() {
+        let mut table = Table::new();
+        table
+            .headers(&["NAME", "SIZE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["foo", "1"])
+            .push_row(["bar", "22"])
+            .push_row(["baz", "333"])
+            .max_rows(2)
+            .column_separator(" | ");
+
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(
+            json,
+            r#"{"headers":["NAME","SIZE"],"alignments":["left","right"],"max_rows":2,"column_separator":" | ","data":[["foo","1"],["bar","22"],["baz","333"]]}"#
+        );
+
+        let restored: Table = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_string(), table.to_string());
+        assert_eq!(restored, table);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_serde_missing_fields() {
+        let table: Table = serde_json::from_str(r#"{"data":[["a","b"]]}"#).unwrap();
+        assert_eq!(table.to_string(), "a  b\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn table_from_json_invalid_shape() {
+        assert!(Table::from_json(&serde_json::json!([]), true).is_none());
+        assert!(Table::from_json(&serde_json::json!({"A": "a"}), true).is_none());
+        assert!(Table::from_json(&serde_json::json!([["a"], {"A": "a"}]), true).is_none());
+    }
+
+    #[test]
+    fn table_with_capacity() {
+        let mut table = Table::with_capacity(50_000, 2);
+        table
+            .headers(&["#", "SQUARE"])
+            .alignments(&[fmt::Alignment::Right; 2]);
+        for i in 0..50_000_u64 {
+            table.push_row([i.to_string(), (i * i).to_string()]);
+        }
+
+        let rendered = table.to_string();
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 50_001);
+        assert_eq!(lines[0], "    #      SQUARE");
+        assert_eq!(lines[1], "    0           0");
+        assert_eq!(lines[50_000], "49999  2499900001");
+    }
+
+    #[test]
+    fn table_with_capacity_renders_like_new() {
+        let mut table = Table::with_capacity(8, 2);
+        table.headers(&["A", "B"]);
+
+        assert_eq!(table.to_string(), "A  B\n");
+        assert_eq!(
+            table.render_grid_skeleton(),
+            Table::new().headers(&["A", "B"]).render_grid_skeleton()
+        );
+    }
+
+    #[test]
+    fn table_push_span_row() {
+        let table = Table::new()
+            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .push_span_row("2023", fmt::Alignment::Left)
+            .push_row(["1.", "---", "---"])
+            .push_row(["2.", "---", "---"])
+            .push_span_row("-- 2024 --", fmt::Alignment::Center)
+            .push_row(["3.", "---", "---"])
+            .push_span_row("end", fmt::Alignment::Right)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+#   COLUMN 1  COLUMN 2
+2023
+1.  ---       ---
+2.  ---       ---
+      -- 2024 --      \n\
+3.  ---       ---
+                   end
+"
+        );
+    }
+
+    #[test]
+    fn table_span_row_does_not_influence_column_width() {
+        let table = Table::new()
+            .push_row(["-", "-"])
+            .push_span_row("this is much wider than the table", fmt::Alignment::Right)
+            .push_row(["-", "-"])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+-  -
+this is much wider than the table
+-  -
+"
+        );
+    }
+
+    #[test]
+    fn table_span_row_with_max_rows() {
+        let table = Table::new()
+            .max_rows(2)
+            .push_span_row("top", fmt::Alignment::Left)
+            .push_row(["1."])
+            .push_span_row("after 1", fmt::Alignment::Left)
+            .push_row(["2."])
+            .push_span_row("elided", fmt::Alignment::Left)
+            .push_row(["3."])
+            .push_span_row("elided", fmt::Alignment::Left)
+            .push_row(["4."])
+            .push_span_row("before 5", fmt::Alignment::Left)
+            .push_row(["5."])
+            .push_span_row("bottom", fmt::Alignment::Left)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+top
+1.
+after 1
+...
+before 5
+5.
+bottom
+"
+        );
+    }
+
+    #[test]
+    fn table_to_dokuwiki() {
+        let table = Table::new()
+            .headers(&["COMMAND", "\x1b[1mPID\x1b[0m", "NOTE"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+                fmt::Alignment::Center,
+            ])
+            .push_row(["rapportd", "449", "-"])
+            .push_span_row("span", fmt::Alignment::Left)
+            .push_row(["Python", "\x1b[92m22396\x1b[0m", "-"])
+            .to_dokuwiki();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+^ COMMAND ^ PID ^ NOTE ^
+| rapportd |  449 |  -  |
+| span |||
+| Python |  22396 |  -  |
+"
+        );
+    }
+
+    #[test]
+    fn table_unified_diff() {
+        let old = Table::new()
+            .headers(&["NAME", "PORT"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["nginx", "80"])
+            .push_row(["postgres", "5432"])
+            .push_row(["redis", "6379"])
+            .to_owned();
+        let new = Table::new()
+            .push_row(["grafana", "3000"])
+            .push_row(["postgres", "5433"])
+            .push_row(["nginx", "80"])
+            .to_owned();
+
+        let diff = old.unified_diff(&new, 0).unwrap();
+
+        println!("{diff}");
+        assert_eq!(
+            diff,
+            "   NAME      PORT
+   nginx       80
+-  postgres  5432
++  postgres  5433
+-  redis     6379
++  grafana   3000
+"
+        );
+    }
+
+    #[test]
+    fn table_unified_diff_identical() {
+        let table = Table::new()
+            .push_row(["a", "1"])
+            .push_row(["b", "2"])
+            .column_separator("|")
+            .to_owned();
+
+        assert_eq!(table.unified_diff(&table, 1).unwrap(), " |a|1\n |b|2\n");
+    }
+
+    #[test]
+    fn table_unified_diff_duplicate_keys() {
+        let old = Table::new()
+            .push_row(["1", "a"])
+            .push_row(["2", "x"])
+            .push_row(["2", "y"])
+            .to_owned();
+        let new = Table::new()
+            .push_row(["1", "a"])
+            .push_row(["1", "b"])
+            .push_row(["2", "z"])
+            .to_owned();
+
+        let diff = old.unified_diff(&new, 0).unwrap();
+
+        println!("{diff}");
+        assert_eq!(diff, "   1  a\n-  2  x\n+  2  z\n-  2  y\n+  1  b\n");
+    }
+
+    #[test]
+    fn table_unified_diff_cleaned_rows() {
+        let old_data = [vec!["a"], vec!["b", "2", "3"]];
+        let new_data = [vec!["a", "", "", "x"], vec![" b ", "2", "4"]];
+        let old = Table::new()
+            .headers(&["A", "B", "C"])
+            .data(&old_data)
+            .pad_short_rows(true)
+            .to_owned();
+        let new = Table::new()
+            .headers(&["A", "B", "C"])
+            .data(&new_data)
+            .truncate_long_rows(true)
+            .trim_cells(true)
+            .to_owned();
+
+        assert_eq!(
+            old.unified_diff(&new, 0).unwrap(),
+            "   A  B  C\n   a     \n-  b  2  3\n+  b  2  4\n"
+        );
+        assert_eq!(
+            old.unified_diff(&new, 1).unwrap(),
+            "   A  B  C\n   a     \n-  b  2  3\n+  b  2  4\n"
+        );
+    }
+
+    #[test]
+    fn table_unified_diff_error_nb_columns_mismatch() {
+        let old = Table::new().push_row(["a", "1"]).to_owned();
+        let new = Table::new().push_row(["b", "2", "x"]).to_owned();
+
+        let error = old.unified_diff(&new, 0).unwrap_err();
+        assert_eq!(
+            error,
+            TableError::DiffColumnsMismatch {
+                nb_columns: 2,
+                nb_other_columns: 3,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "cannot diff a table of 2 columns against one of 3"
+        );
+    }
+
+    #[test]
+    fn table_unified_diff_error_key_column_out_of_range() {
+        let table = Table::new().push_row(["a", "1"]).to_owned();
+
+        assert_eq!(
+            table.unified_diff(&table, 2),
+            Err(TableError::ColumnOutOfRange {
+                index: 2,
+                nb_columns: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn table_unified_diff_error_invalid_table() {
+        let table = Table::new().push_row(["a", "1"]).to_owned();
+        let invalid = Table::new().headers(&["A"]).push_row(["b", "2"]).to_owned();
+
+        assert_eq!(
+            table.unified_diff(&invalid, 0),
+            Err(TableError::ColumnsMismatch)
+        );
+    }
+
+    #[test]
+    fn table_canonical() {
+        let plain = Table::new()
+            .headers(&["NAME", "CHANGE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["foo", "+ 137.89"])
+            .push_row(["barbaz", "- 3.5"])
+            .to_owned();
+        let styled = Table::new()
+            .headers(&["\x1b[1mNAME\x1b[0m", "CHANGE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["foo", "\x1b[92m+ 137.89\x1b[0m"])
+            .push_row(["barbaz", "\x1b[91m- 3.5\x1b[0m"])
+            .column_separator(" | ")
+            .borders(true)
+            .margin_left(2)
+            .zebra("\x1b[2m", "")
+            .fixed_column_widths(&[Some(3), Some(10)])
+            .to_owned();
+
+        assert_ne!(plain.to_string(), styled.to_string());
+        assert_eq!(plain.canonical(), styled.canonical());
+        assert_eq!(
+            plain.canonical(),
+            "\
+NAME     CHANGE
+foo    + 137.89
+barbaz    - 3.5
+"
+        );
+    }
+
+    #[test]
+    fn table_render_grid_skeleton() {
+        let table = Table::new()
+            .headers(&["#", "NAME", "\x1b[1mSTATUS\x1b[0m"])
+            .alignments(&[
+                fmt::Alignment::Right,
+                fmt::Alignment::Left,
+                fmt::Alignment::Center,
+            ])
+            .section("Services")
+            .push_row(["1", "nginx", "✓"])
+            .push_row(["2", "postgres", "✗"])
+            .push_span_row("span", fmt::Alignment::Center)
+            .push_row(["3", "redis", "✓"])
+            .max_rows(2)
+            .borders(true)
+            .zebra("\x1b[2m", "")
+            .to_owned();
 
-        // Don't allocate just yet.
-        let mut output_matches_input = true;
-        let mut out = String::new();
+        let rendered = table.to_string();
+        let skeleton = table.render_grid_skeleton();
+
+        println!("{rendered}");
+        println!("{skeleton}");
+        assert_eq!(
+            skeleton,
+            "\
+┌─────┬───────┬────────┐
+│ ··· │ ····· │ ······ │
+├─────┼───────┼────────┤
+├─ ········ ───────────┤
+│ ··· │ ····· │ ······ │
+│ ··· │ ····· │ ······ │
+│         ····         │
+│ ··· │ ····· │ ······ │
+└─────┴───────┴────────┘
+"
+        );
+        assert_eq!(skeleton.lines().count(), rendered.lines().count());
+        for (skeleton_line, line) in skeleton.lines().zip(rendered.lines()) {
+            assert_eq!(
+                Table::visible_width(skeleton_line),
+                Table::visible_width(line)
+            );
+        }
+    }
+
+    #[test]
+    fn table_render_grid_skeleton_without_borders() {
+        let skeleton = Table::new()
+            .data(&[vec!["a", "bb"], vec!["ccc", "d"]])
+            .push_span_row("span", fmt::Alignment::Right)
+            .column_separator("|")
+            .legend(&[("a", "Letter")])
+            .render_grid_skeleton();
+
+        println!("{skeleton}");
+        assert_eq!(skeleton, "···|··\n···|··\n  ····\n");
+    }
+
+    #[test]
+    fn table_render_grid_skeleton_multi_line_cells() {
+        let table = Table::new()
+            .headers(&["A", "B\nC"])
+            .push_row(["a\nb\nc", "d"])
+            .push_row(["e", "f"])
+            .borders(true)
+            .to_owned();
+
+        let rendered = table.to_string();
+        let skeleton = table.render_grid_skeleton();
+
+        println!("{skeleton}");
+        assert_eq!(skeleton.lines().count(), rendered.lines().count());
+        for (skeleton_line, line) in skeleton.lines().zip(rendered.lines()) {
+            assert_eq!(
+                Table::visible_width(skeleton_line),
+                Table::visible_width(line)
+            );
+        }
+        assert_eq!(
+            skeleton,
+            "\
+┌───┬───┐
+│ · │ · │
+│ · │ · │
+├───┼───┤
+│ · │ · │
+│ · │ · │
+│ · │ · │
+│ · │ · │
+└───┴───┘
+"
+        );
+    }
+
+    #[test]
+    fn table_to_dokuwiki_multi_line_cells() {
+        let table = Table::new()
+            .headers(&["NAME", "DISK\nUSAGE"])
+            .push_row(["alice", "12G\n(80%)"])
+            .to_dokuwiki();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "^ NAME ^ DISK\\\\ USAGE ^\n| alice | 12G\\\\ (80%) |\n"
+        );
+    }
+
+    #[test]
+    fn table_to_dokuwiki_escapes_delimiters() {
+        let table = Table::new()
+            .headers(&["A^B", "C"])
+            .push_row(["a|b", "x\ny|z"])
+            .to_dokuwiki();
+
+        println!("{table}");
+        assert_eq!(table, "^ %%A^B%% ^ C ^\n| %%a|b%% | x\\\\ %%y|z%% |\n");
+    }
+
+    #[test]
+    fn table_to_dokuwiki_without_headers() {
+        let table = Table::new()
+            .max_rows(0)
+            .data(&[vec!["-", "-"]])
+            .to_dokuwiki();
+
+        println!("{table}");
+        assert_eq!(table, "| ... | ... |\n");
+    }
+
+    #[test]
+    fn table_cell_whitespace_is_preserved() {
+        let table = Table::new()
+            .headers(&["L", "R", "C", "L"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+                fmt::Alignment::Center,
+                fmt::Alignment::Left,
+            ])
+            .data(&[vec!["  x", "x  ", " x ", "  x"], vec!["-", "-", "-", "-"]])
+            .column_separator("|")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+L  |  R| C |L
+  x|x  | x |  x
+-  |  -| - |-
+"
+        );
+    }
+
+    #[test]
+    fn table_trim_last_column() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "CENTER"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Center])
+            .push_row(["a", "x"])
+            .push_row(["b", "xy"]);
+
+        assert_eq!(table.to_string(), "A  CENTER\na    x   \nb    xy  \n");
+
+        table.trim_last_column(true);
+        println!("{table}");
+        assert_eq!(table.to_string(), "A  CENTER\na    x\nb    xy\n");
+        assert!(table.to_string().lines().all(|line| !line.ends_with(' ')));
+    }
+
+    #[test]
+    fn table_trim_last_column_right_aligned_with_padding() {
+        let table = Table::new()
+            .headers(&["A", "RIGHT"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["a", "x"])
+            .cell_padding(1, 1)
+            .trim_last_column(true)
+            .to_string();
+
+        assert_eq!(table, " A    RIGHT\n a        x\n");
+    }
+
+    #[test]
+    fn table_without_trailing_newline() {
+        let mut table = Table::new();
+        table.headers(&["A", "B"]).trailing_newline(false);
+
+        assert_eq!(table.to_string(), "A  B");
+
+        table.push_row(["1", "2"]).push_row(["3", "4"]).max_rows(1);
+        assert_eq!(table.to_string(), "A    B\n1    2\n...  ...");
+
+        table.max_output_bytes(1);
+        assert_eq!(table.to_string(), "A    B\n… (output truncated)");
+    }
+
+    #[test]
+    fn table_completely_empty_without_trailing_newline() {
+        let table = Table::new()
+            .headers(&[] as &[&str; 0])
+            .trailing_newline(false)
+            .to_string();
+
+        assert_eq!(table, "");
+    }
+
+    #[test]
+    fn table_line_ending_crlf() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B"])
+            .push_row(["x\ny", "2"])
+            .push_row(["3", "4"])
+            .borders(true)
+            .caption("caption")
+            .line_ending(LineEnding::CrLf);
+
+        let rendered = table.to_string();
+        assert!(rendered.ends_with("\r\n"));
+        assert_eq!(
+            rendered.matches("\r\n").count(),
+            rendered.matches('\n').count()
+        );
+
+        table.trailing_newline(false);
+        let rendered = table.to_string();
+        assert!(!rendered.ends_with('\n'));
+        assert_eq!(
+            rendered.matches("\r\n").count(),
+            rendered.matches('\n').count()
+        );
+    }
+
+    #[test]
+    fn table_ascii_safe() {
+        let mut table = Table::new();
+        table
+            .headers(&["NAME", "SCORE", "X", "Y"])
+            .push_row(["verylongname", "1", "x", "y"])
+            .push_row(["b", "2", "x", "y"])
+            .push_row(["c", "3", "x", "y"])
+            .section("Total")
+            .max_rows(2)
+            .max_cell_width(6)
+            .max_columns(3)
+            .bar_column(1, 3.0, 3)
+            .borders(true)
+            .legend(&[("X", "unknown")])
+            .ascii_safe(true);
+
+        let rendered = table.to_string();
+        println!("{rendered}");
+        assert!(rendered.is_ascii());
+        assert_eq!(
+            rendered,
+            "\
++--------+-------+-----+
+| NAME   | SCORE | ... |
++--------+-------+-----+
+| ver... | #..   | ... |
+| ...    | ...   | ... |
+| c      | ###   | ... |
++- Total --------------+
++--------+-------+-----+
+X - unknown
+"
+        );
+
+        table.max_output_bytes(1);
+        assert!(table.to_string().ends_with("... (output truncated)\n"));
+    }
+
+    #[test]
+    fn table_margin_left() {
+        let table = Table::new()
+            .max_rows(2)
+            .headers(&["#", "COLUMN"])
+            .push_row(["1.", "---"])
+            .push_span_row("span", fmt::Alignment::Left)
+            .push_row(["2.", "---"])
+            .push_row(["3.", "---"])
+            .margin_left(4)
+            .to_owned();
+
+        let rendered = table.to_string();
+
+        println!("{rendered}");
+        assert_eq!(
+            rendered,
+            "    #    COLUMN
+    1.   ---
+    span
+    ...  ...
+    3.   ---
+"
+        );
+        assert!(rendered.lines().all(|line| line.starts_with("    ")));
+        assert_eq!(table.dimensions(), (15, 5));
+    }
+
+    #[test]
+    fn table_margin_left_without_data() {
+        let table = Table::new().headers(&["A", "B"]).margin_left(2).to_string();
+
+        println!("{table}");
+        assert_eq!(table, "  A  B\n");
+    }
+
+    #[test]
+    fn table_dimensions() {
+        let table = Table::new()
+            .headers(&["\x1b[1mA\x1b[0m", "B"])
+            .push_row(["---", "------"])
+            .push_row(["-", "-"])
+            .to_owned();
+
+        assert_eq!(table.dimensions(), (11, 3));
+    }
+
+    #[test]
+    fn table_dimensions_multi_byte_separator() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B", "C"])
+            .push_row(["aaaa", "b", "c"])
+            .column_separator(" │ ");
+
+        // 6 visible characters of content, 3 per separator (5 bytes).
+        assert_eq!(table.dimensions(), (12, 2));
+
+        table.max_width(10);
+        assert_eq!(table.dimensions(), (10, 2));
+        assert_eq!(table.column_widths(), [2, 1, 1]);
+    }
+
+    #[test]
+    fn table_column_widths() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "BB", "C"])
+            .push_row(["---", "-", "-"])
+            .push_row(["-", "-", "------"])
+            .push_row(["-", "-", "-"])
+            .push_row(["-", "-", "-"])
+            .push_row(["-----", "-", "-"])
+            .max_rows(2)
+            .max_cell_width(4);
+
+        // Elided rows do not count, but the elision marker does.
+        let widths = table.column_widths();
+        assert_eq!(widths, [4, 3, 3]);
+
+        let table = table.to_string();
+        let elision = table.lines().find(|line| line.starts_with(ELISION_MARKER));
+        let separators = DEFAULT_COLUMN_SEPARATOR.len() * 2;
+        assert_eq!(
+            elision.map(str::len),
+            Some(widths.iter().sum::<usize>() + separators)
+        );
+    }
+
+    #[test]
+    fn table_header_style() {
+        let mut table = Table::new();
+        table
+            .headers(&["NAME", "N"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["a", "100"]);
+
+        let unstyled = table.to_string();
+        let styled = table.header_style("\x1b[1m", "\x1b[0m").to_string();
+
+        println!("{styled}");
+        assert_eq!(
+            styled,
+            "\
+\x1b[1mNAME\x1b[0m  \x1b[1m  N\x1b[0m
+a     100
+"
+        );
+        assert_eq!(Table::strip_ansi_colors(&styled), unstyled);
+        assert_eq!(styled.lines().nth(1), unstyled.lines().nth(1));
+    }
+
+    #[test]
+    fn table_cell_formatter() {
+        let mut table = Table::new();
+        table
+            .headers(&["ITEM", "DELTA"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["a", "-12"])
+            .push_row(["b", "7"])
+            .push_row(["c", "-3"]);
+
+        let plain = table.to_string();
+        let formatted = table
+            .cell_formatter(&|_, col, value| {
+                (col == 1 && value.starts_with('-'))
+                    .then(|| Cow::Owned(format!("\x1b[31m{value}\x1b[0m")))
+            })
+            .to_string();
+
+        println!("{formatted}");
+        assert_eq!(
+            formatted,
+            "\
+ITEM  DELTA
+a       \x1b[31m-12\x1b[0m
+b         7
+c        \x1b[31m-3\x1b[0m
+"
+        );
+        assert_eq!(Table::strip_ansi_colors(&formatted), plain);
+    }
+
+    #[test]
+    fn table_cell_formatter_positions() {
+        let table = Table::new()
+            .headers(&["A", "B", "C"])
+            .push_row(["a1", "b1", "c1"])
+            .push_row(["a2", "b2", "c2"])
+            .sort_by_column(0, SortOrder::Desc)
+            .columns(&[2, 0])
+            .cell_formatter(&|row, col, _| Some(Cow::Owned(format!("{row}{col}"))))
+            .to_string();
+
+        assert_eq!(table, "C   A\n00  01\n10  11\n");
+    }
+
+    #[test]
+    fn table_cell_formatter_row_after_max_rows() {
+        let data: Vec<Vec<String>> = (1..=6).map(|i| vec![i.to_string()]).collect();
+        let threshold = String::from("5");
+        let table = Table::new()
+            .headers(&["N"])
+            .data(&data)
+            .max_rows(2)
+            .row_numbers(true)
+            .cell_formatter(&|row, col, value| {
+                (col == 1 && value >= threshold.as_str()).then(|| Cow::Owned(row.to_string()))
+            })
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "  #  N\n  1  1\n...  ...\n  6  5\n");
+    }
+
+    #[test]
+    fn table_transpose() {
+        let table = Table::new()
+            .headers(&["COMMAND", "PID", "USER", "HOST:PORTS"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .push_row(["rapportd", "449", "Quentin", "*:61165"])
+            .transpose(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+COMMAND     rapportd
+PID         449
+USER        Quentin
+HOST:PORTS  *:61165
+"
+        );
+    }
+
+    #[test]
+    fn table_transpose_many_rows() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["a1", "b1"])
+            .push_row(["a2", "b2"])
+            .transpose(true)
+            .borders(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+┌───┬────┬────┐
+│ A │ a1 │ a2 │
+│ B │ b1 │ b2 │
+└───┴────┴────┘
+"
+        );
+    }
+
+    #[test]
+    fn table_header_style_with_borders() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["aa", "b"])
+            .header_style("\x1b[7m", "\x1b[0m")
+            .borders(true)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+┌────┬───┐
+│ \x1b[7mA \x1b[0m │ \x1b[7mB\x1b[0m │
+├────┼───┤
+│ aa │ b │
+└────┴───┘
+"
+        );
+    }
+
+    #[test]
+    fn table_legend() {
+        let table = Table::new()
+            .headers(&["TASK", "STATUS", "OWNER"])
+            .data(&[
+                vec!["Write docs", "✓", "Quentin"],
+                vec!["Fix bug", "\x1b[91m✗\x1b[0m", "root"],
+            ])
+            .legend(&[
+                ("✓", "Done"),
+                (
+                    "\x1b[91m✗\x1b[0m",
+                    "Not done, and probably never will be, honestly",
+                ),
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+TASK        STATUS  OWNER
+Write docs  ✓       Quentin
+Fix bug     \x1b[91m✗\x1b[0m       root
+✓ — Done
+\x1b[91m✗\x1b[0m — Not done, and probably
+    never will be, honestly
+"
+        );
+    }
+
+    #[test]
+    fn table_caption_with_timestamp_and_legend() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["✓", "-"])
+            .caption("Tasks")
+            .timestamp_caption(true)
+            .now_fn(|| String::from("2024-01-31 12:00:00 UTC"))
+            .legend(&[("✓", "Done")])
+            .margin_left(1)
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            " A  B
+ ✓  -
+ Tasks
+ 2024-01-31 12:00:00 UTC
+ ✓ — Done
+"
+        );
+    }
 
-        #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
-        while let Some((i, char)) = chars.next() {
-            match (char, &state) {
-                ('\x1b', State::NotInSequence) => {
-                    if let Some((_, char)) = chars.peek() {
-                        if *char == '[' {
-                            state = State::InSequence;
+    #[test]
+    fn table_timestamp_caption_without_caption() {
+        let mut table = Table::new();
+        table
+            .headers(&["A"])
+            .now_fn(|| String::from("now"))
+            .timestamp_caption(true);
 
-                            // From now on, input and output differ.
-                            if output_matches_input {
-                                output_matches_input = false;
-                                // The shortest sequence is 4 chars (`\x1b[0m`).
-                                out.reserve_exact(string.len() - 4);
-                                out = string.chars().take(i).collect();
-                            }
+        assert_eq!(table.to_string(), "A\nnow\n");
+        assert_eq!(table.timestamp_caption(false).to_string(), "A\n");
+    }
 
-                            continue;
-                        }
-                    }
-                }
-                ('m', State::InSequence) => {
-                    state = State::NotInSequence;
-                    continue;
-                }
-                (_, State::NotInSequence) => {}
-                (_, State::InSequence) => continue,
-            };
-            // As long as the output matches the input, this is a no-op.
-            if output_matches_input {
-                continue;
-            }
-            out.push(char);
-        }
+    #[test]
+    fn table_timestamp_caption_default_clock() {
+        let table = Table::new()
+            .headers(&["A"])
+            .timestamp_caption(true)
+            .to_string();
 
-        if output_matches_input {
-            Cow::Borrowed(string)
-        } else {
-            Cow::Owned(out)
-        }
+        let timestamp = table.lines().nth(1).unwrap();
+        assert_eq!(timestamp.len(), "YYYY-MM-DD HH:MM:SS UTC".len());
+        assert!(timestamp.ends_with(" UTC"));
     }
 
-    fn make_table_blueprint(&self) -> TableBlueprint {
-        let nb_cols = self.determine_nb_columns();
+    #[test]
+    fn format_utc_timestamp() {
+        assert_eq!(Table::format_utc_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(
+            Table::format_utc_timestamp(951_782_400), // Leap day.
+            "2000-02-29 00:00:00 UTC"
+        );
+        assert_eq!(
+            Table::format_utc_timestamp(1_700_000_000),
+            "2023-11-14 22:13:20 UTC"
+        );
+        assert_eq!(
+            Table::format_utc_timestamp(4_102_444_799),
+            "2099-12-31 23:59:59 UTC"
+        );
+    }
 
-        let headers = self.get_headers_or_default(nb_cols);
-        let alignments = self.get_alignments_or_default(nb_cols);
-        let mut data = self.get_data_or_default();
+    #[test]
+    fn table_pad_inside_colors() {
+        let table = Table::new()
+            .headers(&["L", "C", "R", "LAST"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Center,
+                fmt::Alignment::Right,
+                fmt::Alignment::Left,
+            ])
+            .push_row([
+                "\x1b[41ma\x1b[0m",
+                "\x1b[1m\x1b[42mb\x1b[0m",
+                "\x1b[43mc\x1b[0m",
+                "\x1b[44md\x1b[0m",
+            ])
+            .push_row(["---", "---", "---", "\x1b[92mplain\x1b[0m!"])
+            .pad_inside_colors(true)
+            .to_string();
 
-        Self::ensure_data_consistency(&headers, &alignments, &data);
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+L     C     R  LAST
+\x1b[41ma  \x1b[0m  \x1b[1m\x1b[42m b \x1b[0m  \x1b[43m  c\x1b[0m  \x1b[44md     \x1b[0m
+---  ---  ---  \x1b[92mplain\x1b[0m!
+"
+        );
+    }
 
-        if let Some(max_rows) = self.max_rows {
-            #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
-            {
-                data = Self::apply_max_rows(data, max_rows, nb_cols);
-            }
-        }
+    #[test]
+    fn table_multi_line_cells() {
+        let mut table = Table::new();
+        table
+            .headers(&["NAME", "ADDRESS", "AGE"])
+            .alignments(&[
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+                fmt::Alignment::Right,
+            ])
+            .push_row(["Alice", "1 Main St\nSpringfield\nUSA", "30"])
+            .push_row(["Bob", "2 Elm St", "4"])
+            .borders(true);
 
-        let columns_width = Self::determine_columns_width(&headers, &data);
-        let column_separator = self.column_separator.unwrap_or(DEFAULT_COLUMN_SEPARATOR);
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+┌───────┬─────────────┬─────┐
+│ NAME  │ ADDRESS     │ AGE │
+├───────┼─────────────┼─────┤
+│ Alice │ 1 Main St   │  30 │
+│       │ Springfield │     │
+│       │ USA         │     │
+│ Bob   │ 2 Elm St    │   4 │
+└───────┴─────────────┴─────┘
+"
+        );
 
-        TableBlueprint {
-            headers,
-            alignments,
-            data,
-            columns_width,
-            column_separator,
-        }
-    }
+        table
+            .borders(false)
+            .vertical_align(VerticalAlignment::Middle);
+        assert_eq!(
+            table.to_string(),
+            "\
+NAME   ADDRESS      AGE
+       1 Main St       \n\
+Alice  Springfield   30
+       USA             \n\
+Bob    2 Elm St       4
+"
+        );
 
-    #[cfg(not(tarpaulin_include))] // Wrongly marked uncovered.
-    fn determine_nb_columns(&self) -> usize {
-        if let Some(headers) = self.headers.as_ref() {
-            return headers.len();
-        }
-        if let Some(data) = self.data.as_ref() {
-            if !data.is_empty() {
-                return data[0].len();
-            }
-        }
-        panic!("headers and data cannot both be empty");
+        table.vertical_align(VerticalAlignment::Bottom);
+        assert_eq!(
+            table.to_string(),
+            "\
+NAME   ADDRESS      AGE
+       1 Main St       \n       Springfield     \n\
+Alice  USA           30
+Bob    2 Elm St       4
+"
+        );
     }
 
-    fn get_headers_or_default(&self, nb_cols: usize) -> Vec<&str> {
-        match self.headers.as_ref() {
-            Some(headers) => headers.to_owned(),
-            // This may look a bit hacky (it is), but it plays nicely
-            // with the overall logic (`Option` would make the code too
-            // convoluted). Moreover, it has the added benefit of
-            // handling the special case where the user does it himself.
-            None => [""].repeat(nb_cols),
-        }
-    }
+    #[test]
+    fn table_multi_line_headers() {
+        let table = Table::new()
+            .headers(&["NAME", "DISK\nUSAGE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["alice", "12G"])
+            .borders(true)
+            .to_string();
 
-    fn get_alignments_or_default(&self, nb_cols: usize) -> Vec<fmt::Alignment> {
-        match self.alignments {
-            Some(alignments) => alignments.to_vec(),
-            None => [fmt::Alignment::Left].repeat(nb_cols),
-        }
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+┌───────┬───────┐
+│ NAME  │  DISK │
+│       │ USAGE │
+├───────┼───────┤
+│ alice │   12G │
+└───────┴───────┘
+"
+        );
     }
 
-    fn get_data_or_default(&self) -> Vec<Vec<&str>> {
-        match self.data.as_ref() {
-            Some(data) => data.to_owned(),
-            None => Vec::new(),
-        }
+    #[test]
+    fn table_multi_line_cells_continuation_lines_are_aligned() {
+        let table = Table::new()
+            .headers(&["N", "X", "Y"])
+            .alignments(&[
+                fmt::Alignment::Right,
+                fmt::Alignment::Left,
+                fmt::Alignment::Left,
+            ])
+            .push_row(["1\n22\n333", "abc", "z"])
+            .column_separator("|")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(table, "  N|X  |Y\n  1|abc|z\n 22|   |\n333|   |\n");
     }
 
-    /// Ensure data is consistent.
-    ///
-    /// "Consistent" essentially means the number of headers matches
-    /// the number of alignment properties, and the number of columns
-    /// in the data.
-    fn ensure_data_consistency(
-        headers: &[&str],
-        alignments: &[fmt::Alignment],
-        data: &[Vec<&str>],
-    ) {
+    #[test]
+    fn table_trim_cells() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B"])
+            .push_row(["  hello  ", "\x1b[1m x \x1b[0m"])
+            .push_row([" \t ", " y"]);
+
+        assert_eq!(table.column_widths(), [9, 3]);
+
+        let table = table.trim_cells(true);
+        assert_eq!(table.column_widths(), [5, 1]);
         assert_eq!(
-            headers.len(),
-            alignments.len(),
-            "number of headers must match alignments"
-        );
-        assert!(
-            data.iter().all(|row| row.len() == headers.len()),
-            "number of headers must match columns in data"
+            table.to_string(),
+            "A      B\nhello  \x1b[1mx\x1b[0m\n       y\n"
         );
     }
 
-    /// Drop rows in the middle to conform to the 'max rows' setting.
-    fn apply_max_rows(mut data: Vec<Vec<&str>>, max_rows: usize, nb_cols: usize) -> Vec<Vec<&str>> {
-        if data.len() <= max_rows {
-            return data; // no-op.
-        }
+    #[test]
+    fn table_preserve_cell_whitespace_overrides_trim_cells() {
+        let mut table = Table::new();
+        table
+            .headers(&["A", "B"])
+            .push_row(["  x", "y"])
+            .trim_cells(true)
+            .preserve_cell_whitespace(true);
 
-        if max_rows == 0 {
-            return vec![["..."].repeat(nb_cols)];
-        }
+        assert_eq!(table.column_widths(), [3, 1]);
+        assert_eq!(table.to_string(), "A    B\n  x  y\n");
 
-        if max_rows == 1 {
-            data.truncate(1);
-            return data
-                .into_iter()
-                .chain(iter::once(["..."].repeat(nb_cols)))
-                .collect();
-        }
+        table.preserve_cell_whitespace(false);
+        assert_eq!(table.column_widths(), [1, 1]);
+        assert_eq!(table.to_string(), "A  B\nx  y\n");
+    }
 
-        // Bias towards more tail elements.
-        let nb_head = max_rows / 2;
-        let nb_tail = max_rows - nb_head;
+    #[test]
+    fn table_sanitize_cells() {
+        let mut table = Table::new();
+        table
+            .headers(&["\u{feff}A", "B"])
+            .push_row(["a\u{200b}b", "x"])
+            .push_row(["c", "\x1b[1my\x07\x1b[0m"]);
 
-        let tail = data.split_off(data.len() - nb_tail);
-        data.truncate(nb_head);
-        let head = data;
+        assert_eq!(table.column_widths(), [3, 2]);
 
-        head.into_iter()
-            .chain(iter::once(["..."].repeat(nb_cols)))
-            .chain(tail)
-            .collect()
+        let table = table.sanitize_cells(true);
+        assert_eq!(table.column_widths(), [2, 1]);
+        assert_eq!(table.to_string(), "A   B\nab  x\nc   \x1b[1my\x1b[0m\n");
     }
 
-    /// Determine the width of each column.
-    ///
-    /// The width of a column is the number of characters in the longest
-    /// value held in the column (including header).
-    fn determine_columns_width(headers: &[&str], data: &[Vec<&str>]) -> Vec<usize> {
-        let mut cols_width = vec![0; headers.len()];
-        for i in 0..headers.len() {
-            let column_values: Vec<&str> = data.iter().map(|x| x[i]).collect();
-            let max_width = Self::width_of_longest_value_in_column(headers[i], &column_values);
-            cols_width[i] = max_width;
-        }
-        cols_width
+    #[test]
+    fn sanitize_cell() {
+        assert_eq!(Table::sanitize_cell(&Cow::Borrowed("a\tb\nc")), None);
+        assert_eq!(
+            Table::sanitize_cell(&Cow::Borrowed("\x1b[41ma\u{200d}\x1b[0m\r")),
+            Some(Cow::Borrowed("\x1b[41ma\x1b[0m"))
+        );
+        assert_eq!(
+            Table::sanitize_cell(&Cow::Borrowed("\x1bx\x1b[")),
+            Some(Cow::Borrowed("x\x1b["))
+        );
     }
 
-    fn width_of_longest_value_in_column(header: &str, column_values: &[&str]) -> usize {
-        let header = iter::once(&header);
-        let column_values = column_values.iter();
+    #[test]
+    fn trim_cell() {
+        assert!(matches!(
+            Table::trim_cell(&Cow::Borrowed(" a ")),
+            Some(Cow::Borrowed("a"))
+        ));
+        assert_eq!(
+            Table::trim_cell(&Cow::Owned(String::from("a "))),
+            Some(Cow::Borrowed("a"))
+        );
+        assert_eq!(
+            Table::trim_cell(&Cow::Borrowed("   ")),
+            Some(Cow::Borrowed(""))
+        );
+        assert_eq!(
+            Table::trim_cell(&Cow::Borrowed(" \x1b[41m a \x1b[0m ")),
+            Some(Cow::Borrowed("\x1b[41ma\x1b[0m"))
+        );
+        assert_eq!(Table::trim_cell(&Cow::Borrowed("a")), None);
+    }
 
-        header
-            .chain(column_values)
-            .map(|x| Self::strip_ansi_colors(x).chars().count())
-            .max()
-            .expect("iterator cannot be empty because header is required")
+    #[test]
+    fn split_colors() {
+        assert_eq!(
+            Table::split_colors("\x1b[1m\x1b[41mfoo\x1b[92mbar\x1b[0m"),
+            Some(("\x1b[1m\x1b[41m", "foo\x1b[92mbar", "\x1b[0m"))
+        );
+        assert_eq!(Table::split_colors("\x1b[0m"), None);
+        assert_eq!(Table::split_colors("foo\x1b[0m"), None);
+        assert_eq!(Table::split_colors("\x1b[41mfoo"), None);
+        assert_eq!(Table::split_colors("\x1b[41;\x1b[0m"), None);
     }
-}
 
-impl fmt::Display for Table<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.render(f)
+    #[test]
+    fn table_max_output_bytes() {
+        let data: Vec<Vec<String>> = (0..1000)
+            .map(|i| vec![i.to_string(), "-".repeat(100)])
+            .collect();
+
+        let table = Table::new()
+            .headers(&["#", "DATA"])
+            .data(&data)
+            .legend(&[("#", "Row number")])
+            .margin_left(1)
+            .max_output_bytes(250)
+            .to_string();
+
+        println!("{table}");
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[3].starts_with(" 2    ---"));
+        assert_eq!(lines[4], " … (output truncated)");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn table_max_output_bytes_not_exceeded() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a"])
+            .max_output_bytes(4)
+            .to_string();
+
+        assert_eq!(table, "A\na\n");
+    }
 
     #[test]
-    fn table_default_builder() {
-        assert_eq!(Table::new(), Table::default());
+    fn prepared_max_output_bytes_is_reset_between_renders() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a"])
+            .max_output_bytes(1)
+            .to_owned();
+        let prepared = table.prepare();
+
+        assert_eq!(prepared.to_string(), "A\n… (output truncated)\n");
+        assert_eq!(prepared.to_string(), "A\n… (output truncated)\n");
     }
 
     #[test]
-    fn table_regular() {
+    fn table_strip_colors() {
         let table = Table::new()
-            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-            ])
-            .data(&[
-                vec![
-                    "Value larger than header",
-                    "Column name has space",
-                    "No trailing whitespace",
-                ],
-                vec!["---", "---", "---"],
-            ])
+            .headers(&["\x1b[1mNAME\x1b[0m", "CHANGE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["foo", "\x1b[92m+ 137.89\x1b[0m"])
+            .push_row(["\x1b[91mbarbaz\x1b[0m", "\x1b[91m- 3.5\x1b[0m"])
+            .push_span_row("\x1b[2mspan\x1b[0m", fmt::Alignment::Right)
+            .zebra("\x1b[2m", "\x1b[7m")
+            .legend(&[("\x1b[92m+\x1b[0m", "Up")])
+            .strip_colors(true)
             .to_string();
 
         println!("{table}");
+        assert!(!table.contains('\x1b'));
         assert_eq!(
             table,
             "\
-SHORT                     WITH SPACE             LAST COLUMN
-Value larger than header  Column name has space  No trailing whitespace
----                       ---                    ---
+NAME      CHANGE
+foo     + 137.89
+barbaz     - 3.5
+            span
++ — Up
 "
         );
     }
 
     #[test]
-    fn table_single_column() {
+    fn table_legend_without_data() {
         let table = Table::new()
-            .headers(&["foo"])
-            .data(&[vec!["bar"], vec!["baz"]])
-            .column_separator("|")
+            .headers(&["A", "B"])
+            .legend(&[("*", "Starred")])
             .to_string();
 
         println!("{table}");
+        assert_eq!(table, "A  B\n* — Starred\n");
+    }
+
+    #[test]
+    fn wrap_words() {
+        let wrap = Table::wrap_words;
+
+        assert_eq!(wrap("", 10), [""]);
+        assert_eq!(wrap("hello world", 11), ["hello world"]);
+        assert_eq!(wrap("hello world", 10), ["hello", "world"]);
+        assert_eq!(wrap("  hello   world  ", 20), ["hello world"]);
+        assert_eq!(wrap("a verylongword b", 4), ["a", "verylongword", "b"]);
         assert_eq!(
-            table,
-            "\
-foo
-bar
-baz
-"
+            wrap("\x1b[1mhello\x1b[0m world", 11),
+            ["\x1b[1mhello\x1b[0m world"]
         );
     }
 
-    #[test]
-    fn table_without_data() {
-        let table = Table::new()
-            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-            ])
+    #[test]
+    fn table_header_case_upper() {
+        let table = Table::new()
+            .headers(&["command", "pid"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .data(&[vec!["foo", "1"]])
+            .header_transform(HeaderCase::Upper)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-SHORT  WITH SPACE  LAST COLUMN
+COMMAND  PID
+foo        1
 "
         );
     }
 
     #[test]
-    fn table_all_empty_headers_not_rendered() {
+    fn table_header_case_changes_column_width() {
+        // 'ß' upper-cases to 'SS', widening the column.
         let table = Table::new()
-            .headers(&["", ""])
-            .data(&[vec!["---", "----------------"]])
+            .headers(&["straße", "x"])
+            .data(&[vec!["-", "-"]])
+            .header_transform(HeaderCase::Upper)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
----  ----------------
+STRASSE  X
+-        -
 "
         );
     }
 
     #[test]
-    fn table_some_empty_headers_all_rendered() {
+    fn table_header_case_leaves_data_untouched() {
         let table = Table::new()
-            .headers(&["", "-"])
-            .data(&[vec!["---", "----------------"]])
+            .headers(&["NAME"])
+            .data(&[vec!["Value"]])
+            .header_transform(HeaderCase::Lower)
             .to_string();
 
-        println!("{table}");
+        assert_eq!(table, "name\nValue\n");
+    }
+
+    #[test]
+    fn apply_header_case() {
+        let case = Table::apply_header_case;
+
+        assert_eq!(case("host:PORTS", HeaderCase::AsIs), "host:PORTS");
+        assert_eq!(case("host:PORTS", HeaderCase::Upper), "HOST:PORTS");
+        assert_eq!(case("host:PORTS", HeaderCase::Lower), "host:ports");
+        assert_eq!(case("hOST nAME", HeaderCase::Title), "Host Name");
+        assert_eq!(case("", HeaderCase::Title), "");
+
+        // ANSI sequences are left intact.
         assert_eq!(
-            table,
-            r"     -
----  ----------------
-"
+            case("\x1b[1mpid\x1b[0m", HeaderCase::Upper),
+            "\x1b[1mPID\x1b[0m"
+        );
+        assert_eq!(
+            case("\x1b[1mPID\x1b[0m", HeaderCase::Lower),
+            "\x1b[1mpid\x1b[0m"
+        );
+        assert_eq!(
+            case("\x1b[1mhost\x1b[0m name", HeaderCase::Title),
+            "\x1b[1mHost\x1b[0m Name"
         );
     }
 
     #[test]
-    fn table_default_headers() {
+    fn prepared_renders_like_table() {
         let table = Table::new()
-            .data(&[vec!["---", "----------------"]])
-            .to_string();
+            .headers(&["A", "B"])
+            .push_row(["---", "---"])
+            .to_owned();
 
-        println!("{table}");
-        assert_eq!(
-            table,
-            "\
----  ----------------
-"
-        );
+        assert_eq!(table.prepare().to_string(), table.to_string());
     }
 
     #[test]
-    fn table_headers_alignment() {
+    fn prepared_concat_widens_columns() {
         let table = Table::new()
-            .headers(&["ALIGN-LEFT", "ALIGN-CENTER", "ALIGN-RIGHT"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Center,
-                fmt::Alignment::Right,
-            ])
-            .data(&[
-                vec![
-                    "Header is aligned Left",
-                    "Header is aligned Center",
-                    "Header is aligned Right",
-                ],
-                vec!["---", "---", "---"],
-            ])
-            .to_string();
+            .headers(&["#", "VALUE"])
+            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Right])
+            .push_row(["1.", "---"])
+            .to_owned();
 
-        println!("{table}");
+        let mut prepared = table.prepare();
+        assert_eq!(prepared.to_string(), "#   VALUE\n1.    ---\n");
+
+        prepared.concat(&[vec!["2.", "----------"]]).unwrap();
+
+        println!("{prepared}");
         assert_eq!(
-            table,
+            prepared.to_string(),
             "\
-ALIGN-LEFT                    ALIGN-CENTER                    ALIGN-RIGHT
-Header is aligned Left  Header is aligned Center  Header is aligned Right
----                               ---                                 ---
+#        VALUE
+1.         ---
+2.  ----------
 "
         );
+
+        // Narrower rows do not shrink columns.
+        prepared.concat(&[vec!["3.", "-"]]).unwrap();
+        assert_eq!(prepared.to_string().lines().last(), Some("3.           -"));
     }
 
     #[test]
-    fn table_values_alignment() {
+    fn prepared_concat_converts_rows_like_table() {
         let table = Table::new()
-            .headers(&["ALIGN-LEFT", "ALIGN-CENTER", "ALIGN-RIGHT"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Center,
-                fmt::Alignment::Right,
-            ])
-            .data(&[vec!["Left", "Center", "Right"], vec!["---", "---", "---"]])
-            .to_string();
+            .headers(&["NAME", "SIZE"])
+            .push_row(["a", "1234"])
+            .group_numeric(&[1], ',')
+            .trim_cells(true)
+            .row_numbers(true)
+            .escape_separator_collisions(true)
+            .column_separator(" | ")
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec![" b | c ", "123456"]]).unwrap();
 
-        println!("{table}");
         assert_eq!(
-            table,
+            prepared.to_string(),
             "\
-ALIGN-LEFT  ALIGN-CENTER  ALIGN-RIGHT
-Left           Center           Right
----             ---               ---
+# | NAME   | SIZE
+1 | a      | 1,234
+2 | b\\ | c | 123,456
 "
         );
     }
 
     #[test]
-    fn table_default_alignments() {
+    fn prepared_concat_selects_columns() {
         let table = Table::new()
-            .headers(&["VALUE LEFT", "COLUMN LEFT"])
-            .data(&[vec!["---", "----------------"]])
+            .headers(&["A", "B", "C"])
+            .push_row(["a", "b", "c"])
+            .columns(&[2, 0])
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec!["aa", "bb", "cc"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "C   A\nc   a\ncc  aa\n");
+    }
+
+    #[test]
+    fn prepared_concat_truncates_to_width_limits() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["a", "b"])
+            .fixed_column_widths(&[Some(3), None])
+            .max_cell_width(5)
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec!["aaaaaa", "bbbbbbbb"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "A    B\na    b\naa…  bbbb…\n");
+    }
+
+    #[test]
+    fn prepared_concat_stretch_last_column() {
+        let table = Table::new()
+            .push_row(["a", "b"])
+            .max_width(12)
+            .stretch_last_column(true)
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec!["aaaaaaa", "b"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "a        b  \naaaaaaa  b  \n");
+    }
+
+    #[test]
+    fn prepared_concat_lines_up_decimals() {
+        let table = Table::new()
+            .headers(&["N"])
+            .push_row(["12.5"])
+            .decimal_align(&[0])
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec!["3"], vec!["1.5"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "N\n12.5\n 3  \n 1.5\n");
+    }
+
+    #[test]
+    fn prepared_concat_continues_group_boundaries() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a"])
+            .group_boundaries(&[1])
+            .to_owned();
+
+        let mut prepared = table.prepare();
+        prepared.concat(&[vec!["b"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "A\na\n─\nb\n");
+    }
+
+    #[test]
+    fn prepared_concat_error_nb_headers_neq_nb_columns() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .push_row(["a", "b"])
+            .to_owned();
+        let mut prepared = table.prepare();
+
+        let error = prepared.concat(&[vec!["a", "b"], vec!["---"]]).map(|_| ());
+
+        assert_eq!(error, Err(TableError::ColumnsMismatch));
+        assert_eq!(prepared.to_string(), table.to_string());
+    }
+
+    #[test]
+    fn prepared_concat_accepts_rows_before_row_numbers() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a"])
+            .row_numbers(true)
+            .to_owned();
+        let mut prepared = table.prepare();
+
+        prepared.concat(&[vec!["b"]]).unwrap();
+
+        assert_eq!(prepared.to_string(), "#  A\n1  a\n2  b\n");
+    }
+
+    #[test]
+    fn prepared_concat_error_transposed() {
+        let table = Table::new()
+            .headers(&["A"])
+            .push_row(["a"])
+            .transpose(true)
+            .to_owned();
+
+        let error = table.prepare().concat(&[vec!["b"]]).map(|_| ());
+
+        assert_eq!(error, Err(TableError::ConcatTransposed));
+    }
+
+    #[test]
+    fn table_borders() {
+        let table = Table::new()
+            .headers(&["#", "NAME"])
+            .alignments(&[fmt::Alignment::Right, fmt::Alignment::Left])
+            .push_row(["1", "foo"])
+            .push_row(["10", "barbaz"])
+            .borders(true)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-VALUE LEFT  COLUMN LEFT
----         ----------------
+┌────┬────────┐
+│  # │ NAME   │
+├────┼────────┤
+│  1 │ foo    │
+│ 10 │ barbaz │
+└────┴────────┘
 "
         );
     }
 
     #[test]
-    fn table_default_headers_and_alignments() {
-        let table = Table::new()
-            .data(&[
-                vec!["---", "----------------"],
-                vec!["----------------", "---"],
-            ])
-            .to_string();
+    fn table_borders_without_data() {
+        let table = Table::new().headers(&["A", "B"]).borders(true).to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
----               ----------------
-----------------  ---
+┌───┬───┐
+│ A │ B │
+└───┴───┘
 "
         );
     }
 
     #[test]
-    fn table_with_empty_data() {
+    fn table_borders_with_span_row_and_elision() {
         let table = Table::new()
-            .headers(&["SHORT", "WITH SPACE", "LAST COLUMN"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-            ])
-            .data(&[] as &[Vec<&str>; 0])
+            .max_rows(1)
+            .headers(&["KEY", "VALUE"])
+            .push_row(["a", "1"])
+            .push_span_row("very long span row", fmt::Alignment::Right)
+            .push_row(["b", "2"])
+            .borders(true)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-SHORT  WITH SPACE  LAST COLUMN
+┌─────┬───────┐
+│ KEY │ VALUE │
+├─────┼───────┤
+│ a   │ 1     │
+│ very long … │
+│ ... │ ...   │
+└─────┴───────┘
 "
         );
     }
 
     #[test]
-    fn table_completely_empty() {
+    fn table_section_with_borders() {
         let table = Table::new()
-            .headers(&[] as &[&str; 0])
-            .alignments(&[])
-            .data(&[] as &[Vec<&str>; 0])
+            .headers(&["KEY", "VALUE"])
+            .push_row(["a", "1"])
+            .section("Details")
+            .push_row(["b", "2"])
+            .section("This title is way too long")
+            .push_row(["c", "3"])
+            .borders(true)
             .to_string();
 
         println!("{table}");
-        assert_eq!(table, "\n");
+        assert_eq!(
+            table,
+            "\
+┌─────┬───────┐
+│ KEY │ VALUE │
+├─────┼───────┤
+│ a   │ 1     │
+├─ Details ───┤
+│ b   │ 2     │
+├─ This tit… ─┤
+│ c   │ 3     │
+└─────┴───────┘
+"
+        );
     }
 
     #[test]
-    #[should_panic(expected = "headers and data cannot both be empty")]
-    fn table_error_completely_empty_with_default_headers() {
+    fn table_section_without_borders() {
         let table = Table::new()
-            .alignments(&[])
-            .data(&[] as &[Vec<&str>; 0])
+            .headers(&["KEY", "VALUE"])
+            .section("Group 1")
+            .push_row(["a", "----------"])
+            .section("Group 2")
+            .push_row(["b", "-"])
             .to_string();
 
         println!("{table}");
-        assert_eq!(table, "\n");
+        assert_eq!(
+            table,
+            "\
+KEY  VALUE
+── Group 1 ────
+a    ----------
+── Group 2 ────
+b    -
+"
+        );
     }
 
     #[test]
-    fn table_completely_empty_with_default_alignments() {
-        let table = Table::new()
-            .headers(&[] as &[&str; 0])
-            .data(&[] as &[Vec<&str>; 0])
-            .to_string();
-
-        println!("{table}");
-        assert_eq!(table, "\n");
-    }
+    fn table_section_narrow_table() {
+        let mut table = Table::new();
+        table.headers(&["A"]).section("Title").push_row(["a"]);
 
-    #[test]
-    #[should_panic(expected = "headers and data cannot both be empty")]
-    fn table_error_completely_empty_with_default_headers_and_alignments() {
-        let table = Table::new().data(&[] as &[Vec<&str>; 0]).to_string();
+        assert_eq!(table.to_string(), "A\n─\na\n");
 
+        table.borders(true);
         println!("{table}");
-        assert_eq!(table, "\n");
-    }
+        assert_eq!(
+            table.to_string(),
+            "\
+┌───┐
+│ A │
+├───┤
+├───┤
+│ a │
+└───┘
+"
+        );
 
-    #[test]
-    #[should_panic(expected = "number of headers must match alignments")]
-    fn table_error_nb_headers_neq_nb_alignments() {
-        Table::new()
-            .headers(&["COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-            ])
-            .data(&[vec!["---", "---"]])
-            .to_string();
+        table.headers(&["ABCDEF"]).borders(false);
+        assert_eq!(table.to_string(), "ABCDEF\n── … ─\na\n");
+        table.headers(&["ABCDEFG"]);
+        assert_eq!(table.to_string(), "ABCDEFG\n── T… ─\na\n");
     }
 
     #[test]
-    #[should_panic(expected = "number of headers must match columns in data")]
-    fn table_error_nb_headers_neq_nb_columns_in_data() {
-        Table::new()
-            .headers(&["COLUMN 1", "COLUMN 2"])
-            .alignments(&[fmt::Alignment::Left, fmt::Alignment::Left])
-            .data(&[
-                vec!["---", "---"],
-                vec!["---", "---", "---"],
-                vec!["---", "---"],
-            ])
-            .to_string();
-    }
+    fn table_group_boundaries() {
+        let mut table = Table::new();
+        table
+            .headers(&["CATEGORY", "ITEM"])
+            .push_row(["fruit", "apple"])
+            .push_row(["fruit", "banana"])
+            .push_row(["fruit", "cherry"])
+            .push_row(["vegetable", "leek"])
+            .push_row(["vegetable", "pea"])
+            .group_boundaries(&[3, 42]);
 
-    #[test]
-    fn table_max_rows_regular() {
-        let table = Table::new()
-            .max_rows(5)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
-            .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "---", "---"],
-                vec!["3.", "------------", "------------"],
-                vec!["4.", "------------", "------------"],
-                vec!["5.", "---", "---"],
-                vec!["6.", "---", "---"],
-                vec!["7.", "---", "---"],
-            ])
-            .to_string();
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+CATEGORY   ITEM
+fruit      apple
+fruit      banana
+fruit      cherry
+─────────  ──────
+vegetable  leek
+vegetable  pea
+"
+        );
 
+        table.max_rows(2).borders(true);
         println!("{table}");
         assert_eq!(
-            table,
+            table.to_string(),
             "\
-#    COLUMN 1  COLUMN 2
-1.   ---            ---
-2.   ---            ---
-...  ...            ...
-5.   ---            ---
-6.   ---            ---
-7.   ---            ---
+┌───────────┬───────┐
+│ CATEGORY  │ ITEM  │
+├───────────┼───────┤
+│ fruit     │ apple │
+│ ...       │ ...   │
+│ vegetable │ pea   │
+└───────────┴───────┘
+"
+        );
+
+        table.max_rows(4);
+        assert_eq!(table.to_string().matches('├').count(), 2);
+    }
+
+    #[test]
+    fn table_group_boundaries_follow_sorted_rows() {
+        let mut table = Table::new();
+        table
+            .headers(&["KEY", "N"])
+            .push_row(["b", "2"])
+            .push_row(["a", "1"])
+            .push_row(["d", "4"])
+            .push_row(["c", "3"])
+            .group_boundaries(&[1, 2])
+            .sort_by_column(0, SortOrder::Asc);
+
+        // Row 1 (`a`) comes first once sorted, there is nothing above.
+        println!("{table}");
+        assert_eq!(
+            table.to_string(),
+            "\
+KEY  N
+a    1
+b    2
+c    3
+───  ─
+d    4
 "
         );
     }
 
     #[test]
-    fn table_max_rows_smallest_regular_case() {
+    fn table_section_to_dokuwiki() {
         let table = Table::new()
-            .max_rows(2)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
+            .headers(&["A", "B"])
+            .section("Section")
+            .push_row(["-", "-"])
+            .to_dokuwiki();
+
+        assert_eq!(table, "^ A ^ B ^\n^ Section ^^\n| - | - |\n");
+    }
+
+    #[test]
+    fn table_cell_padding() {
+        let table = Table::new()
+            .headers(&["A", "B", "C"])
             .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
                 fmt::Alignment::Right,
+                fmt::Alignment::Center,
+                fmt::Alignment::Left,
             ])
-            .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "---", "---"],
-                vec!["3.", "---", "---"],
-            ])
-            .to_string();
+            .push_row(["aa", "bbb", "c"])
+            .column_separator("|")
+            .cell_padding(1, 1)
+            .to_owned();
 
-        println!("{table}");
+        let rendered = table.to_string();
+
+        println!("{rendered}");
         assert_eq!(
-            table,
-            "\
-#    COLUMN 1  COLUMN 2
-1.   ---            ---
-...  ...            ...
-3.   ---            ---
+            rendered,
+            "  A |  B  | C
+ aa | bbb | c
 "
         );
+        assert_eq!(table.dimensions(), (13, 2));
     }
 
     #[test]
-    fn table_max_rows_elided_rows_do_not_impact_column_width() {
+    fn table_cell_padding_with_borders() {
         let table = Table::new()
-            .max_rows(1)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
-            .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "------------", "------------"],
-            ])
-            .to_string();
+            .headers(&["A", "B"])
+            .push_row(["aa", "b"])
+            .push_span_row("span", fmt::Alignment::Right)
+            .cell_padding(2, 1)
+            .borders(true)
+            .to_owned();
 
-        println!("{table}");
+        let rendered = table.to_string();
+
+        println!("{rendered}");
         assert_eq!(
-            table,
+            rendered,
             "\
-#    COLUMN 1  COLUMN 2
-1.   ---            ---
-...  ...            ...
+┌───────┬──────┐
+│   A   │   B  │
+├───────┼──────┤
+│   aa  │   b  │
+│         span │
+└───────┴──────┘
 "
         );
+        assert_eq!(table.dimensions(), (16, 6));
     }
 
     #[test]
-    fn table_max_rows_gt_nb_rows() {
+    fn table_max_cell_width() {
         let table = Table::new()
-            .max_rows(8)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
+            .headers(&["NAME", "DESCRIPTION"])
             .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "---", "---"],
-                vec!["3.", "------------", "------------"],
-                vec!["4.", "------------", "------------"],
-                vec!["5.", "---", "---"],
-                vec!["6.", "---", "---"],
-                vec!["7.", "---", "---"],
+                vec!["verynicetable", "Number one table."],
+                vec!["foo", "\x1b[92mBar baz qux.\x1b[0m"],
             ])
+            .max_cell_width(8)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-#   COLUMN 1          COLUMN 2
-1.  ---                    ---
-2.  ---                    ---
-3.  ------------  ------------
-4.  ------------  ------------
-5.  ---                    ---
-6.  ---                    ---
-7.  ---                    ---
+NAME      DESCRIPTION
+verynic…  Number …
+foo       \x1b[92mBar baz…\x1b[0m
 "
         );
     }
 
     #[test]
-    fn table_max_rows_eq_nb_rows() {
+    fn table_max_cell_width_multi_line_cells() {
         let table = Table::new()
-            .max_rows(7)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
+            .headers(&["A"])
+            .push_row(["ab\ncd"])
+            .push_row(["abcd\nef"])
+            .max_cell_width(3)
+            .to_string();
+
+        assert_eq!(table, "A\nab\ncd\nab…\nef\n");
+    }
+
+    #[test]
+    fn table_column_overflow_overrides_max_cell_width() {
+        let table = Table::new()
+            .headers(&["NAME", "DESCRIPTION", "TAGS"])
             .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "---", "---"],
-                vec!["3.", "------------", "------------"],
-                vec!["4.", "------------", "------------"],
-                vec!["5.", "---", "---"],
-                vec!["6.", "---", "---"],
-                vec!["7.", "---", "---"],
+                vec!["verynicetable", "Number one table.", "tui, table"],
+                vec!["foo", "Bar.", "-"],
             ])
+            .max_cell_width(5)
+            .column_overflow(&[Overflow::Truncate, Overflow::None])
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-#   COLUMN 1          COLUMN 2
-1.  ---                    ---
-2.  ---                    ---
-3.  ------------  ------------
-4.  ------------  ------------
-5.  ---                    ---
-6.  ---                    ---
-7.  ---                    ---
+NAME   DESCRIPTION        TAGS
+very…  Number one table.  tui,…
+foo    Bar.               -
 "
         );
     }
 
     #[test]
-    fn table_max_rows_max_zero() {
+    fn table_column_overflow_without_max_cell_width() {
         let table = Table::new()
-            .max_rows(0)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
-            .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "------------", "------------"],
-            ])
+            .data(&[vec!["verynicetable", "table"]])
+            .column_overflow(&[Overflow::Truncate, Overflow::Truncate])
             .to_string();
 
-        println!("{table}");
+        assert_eq!(table, "verynicetable  table\n");
+    }
+
+    #[test]
+    fn table_max_width_shrinks_widest_columns() {
+        let table = Table::new()
+            .headers(&["NAME", "DESCRIPTION"])
+            .push_row(["verynicetable", "A table"])
+            .max_width(20)
+            .to_owned();
+
+        let rendered = table.to_string();
+
+        println!("{rendered}");
         assert_eq!(
-            table,
+            rendered,
             "\
-#    COLUMN 1  COLUMN 2
-...  ...            ...
+NAME       DESCRIPT…
+verynice…  A table
 "
         );
+        assert_eq!(table.dimensions(), (20, 2));
     }
 
     #[test]
-    fn table_max_rows_max_zero_with_empty_data() {
+    fn table_max_width_shrinks_elision_marker() {
         let table = Table::new()
-            .max_rows(0)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
-            .data(&[] as &[Vec<&str>; 0])
+            .headers(&["A", "B"])
+            .data(&[vec!["-", "-"], vec!["-", "-"], vec!["-", "-"]])
+            .max_rows(2)
+            .max_width(6)
             .to_string();
 
         println!("{table}");
         assert_eq!(
             table,
             "\
-#  COLUMN 1  COLUMN 2
+A   B
+-   -
+.…  .…
+-   -
 "
         );
     }
 
     #[test]
-    fn table_max_rows_max_zero_without_header() {
-        // It is forbidden to have both empty headers and empty data.
-        // Here we render with a 100% valid table, but clear the data
-        // through `max_rows(0)`.
-        let table = Table::new()
-            .max_rows(0)
-            .data(&[vec!["---", "----------------"]])
-            .to_string();
+    fn table_max_width_multi_line_cells() {
+        let mut table = Table::new();
+        table.headers(&["A"]).push_row(["abcd\nef"]).max_width(6);
 
-        println!("{table}");
-        assert_eq!(table, "...  ...\n");
+        assert_eq!(table.to_string(), "A\nabcd\nef\n");
+
+        table.max_width(3);
+        assert_eq!(table.to_string(), "A\nab…\nef\n");
     }
 
     #[test]
-    fn table_max_rows_max_one() {
+    fn table_stretch_last_column_fills_max_width() {
         let table = Table::new()
-            .max_rows(1)
-            .headers(&["#", "COLUMN 1", "COLUMN 2"])
-            .alignments(&[
-                fmt::Alignment::Left,
-                fmt::Alignment::Left,
-                fmt::Alignment::Right,
-            ])
-            .data(&[
-                vec!["1.", "---", "---"],
-                vec!["2.", "------------", "------------"],
-                vec!["3.", "---", "---"],
-            ])
-            .to_string();
+            .headers(&["#", "NAME"])
+            .push_row(["1", "foo"])
+            .max_width(12)
+            .stretch_last_column(true)
+            .to_owned();
 
-        println!("{table}");
+        let rendered = table.to_string();
+
+        println!("{rendered}");
         assert_eq!(
-            table,
+            rendered,
             "\
-#    COLUMN 1  COLUMN 2
-1.   ---            ---
-...  ...            ...
+#  NAME     \n\
+1  foo      \n\
 "
         );
+        assert_eq!(table.dimensions(), (12, 2));
     }
 
     #[test]
-    fn table_column_separator() {
+    fn table_stretch_last_column_with_borders_and_margin() {
         let table = Table::new()
-            .headers(&["1", "2", "3"])
-            .alignments(&[
-                fmt::Alignment::Right,
-                fmt::Alignment::Center,
-                fmt::Alignment::Left,
-            ])
-            .data(&[
-                vec!["---", "---", "---"],
-                vec!["------", "------", "------"],
-                vec!["---", "---", "---"],
-            ])
-            .column_separator("|")
-            .to_string();
+            .headers(&["#", "NAME"])
+            .push_row(["1", "foo"])
+            .borders(true)
+            .margin_left(2)
+            .max_width(16)
+            .stretch_last_column(true)
+            .to_owned();
 
-        println!("{table}");
+        let rendered = table.to_string();
+
+        println!("{rendered}");
         assert_eq!(
-            table,
-            "     1|  2   |3
-   ---| ---  |---
-------|------|------
-   ---| ---  |---
+            rendered,
+            "  ┌───┬────────┐
+  │ # │ NAME   │
+  ├───┼────────┤
+  │ 1 │ foo    │
+  └───┴────────┘
 "
         );
+        assert_eq!(table.dimensions(), (16, 5));
     }
 
     #[test]
-    fn table_render_multiple_times() {
-        let data = [vec!["---"]];
+    fn table_stretch_last_column_is_noop_if_already_wide_enough() {
         let table = Table::new()
-            .headers(&["HEADER"])
-            .alignments(&[fmt::Alignment::Left])
-            .data(&data)
-            .to_owned();
-
-        let render_1 = table.to_string();
-        let render_2 = table.to_string();
-
-        println!("{table}");
+            .headers(&["#", "NAME"])
+            .data(&[vec!["1", "foo"]])
+            .max_width(7)
+            .stretch_last_column(true)
+            .to_string();
 
-        assert_eq!(render_1, "HEADER\n---\n");
-        assert_eq!(render_1, render_2);
+        assert_eq!(table, "#  NAME\n1  foo\n");
     }
 
     #[test]
@@ -1183,6 +7889,114 @@ SHORT  WITH SPACE  LAST COLUMN
         );
     }
 
+    #[test]
+    fn table_zebra() {
+        let table = Table::new()
+            .headers(&["A", "B"])
+            .data(&[
+                vec!["0", "-"],
+                vec!["1", "-"],
+                vec!["2", "-"],
+                vec!["3", "-"],
+            ])
+            .zebra("\x1b[2m", "\x1b[7m")
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "\
+A  B
+\x1b[2m0  -\x1b[0m
+\x1b[7m1  -\x1b[0m
+\x1b[2m2  -\x1b[0m
+\x1b[7m3  -\x1b[0m
+"
+        );
+    }
+
+    #[test]
+    fn table_grapheme_clusters_count_as_one_character() {
+        let table = Table::new()
+            .headers(&["NAME", "X"])
+            .alignments(&[fmt::Alignment::Right, fmt::Alignment::Left])
+            .data(&[
+                vec!["Cafe\u{301}", "-"], // Decomposed 'é'.
+                vec!["Cafe", "-"],
+                vec!["Coffee", "-"],
+            ])
+            .to_string();
+
+        println!("{table}");
+        assert_eq!(
+            table,
+            "  NAME  X
+  Cafe\u{301}  -
+  Cafe  -
+Coffee  -
+"
+        );
+    }
+
+    #[test]
+    fn visible_width() {
+        let width = Table::visible_width;
+
+        assert_eq!(width(""), 0);
+        assert_eq!(width("hello"), 5);
+        assert_eq!(width("\x1b[92mhello\x1b[0m"), 5);
+
+        // Multi-byte characters.
+        assert_eq!(width("Café"), 4);
+        assert_eq!(width("─│┼"), 3);
+
+        // Combining marks.
+        assert_eq!(width("Cafe\u{301}"), 4);
+        assert_eq!(width("\x1b[1me\x1b[0m\u{301}"), 1);
+        assert_eq!(width("a\u{301}\u{323}"), 1);
+
+        // Emoji sequences.
+        assert_eq!(width("\u{2764}\u{fe0f}"), 1); // Heart + variation selector.
+        assert_eq!(width("\u{1f44d}\u{1f3fd}"), 1); // Thumbs up + skin tone.
+        assert_eq!(width("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"), 1); // Family.
+
+        // Flags.
+        assert_eq!(width("\u{1f1eb}\u{1f1f7}"), 1);
+        assert_eq!(width("\u{1f1eb}\u{1f1f7}\u{1f1e9}\u{1f1ea}"), 2);
+        assert_eq!(width("\u{1f1eb}\u{1f1f7}\u{1f1e9}"), 2);
+    }
+
+    #[test]
+    fn truncate() {
+        fn truncate(string: &str, width: usize) -> Cow<'_, str> {
+            Table::truncate(string, width, "…")
+        }
+
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 4), "hel…");
+        assert_eq!(truncate("hello", 1), "…");
+        assert_eq!(truncate("hello", 0), "");
+        assert_eq!(truncate("", 0), "");
+
+        // Width is in grapheme clusters.
+        assert_eq!(truncate("Cafe\u{301}s", 5), "Cafe\u{301}s");
+        assert_eq!(truncate("Cafe\u{301}s", 4), "Caf…");
+        assert_eq!(truncate("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+
+        // ANSI sequences are kept, and closed.
+        assert_eq!(truncate("\x1b[92mhello\x1b[0m", 5), "\x1b[92mhello\x1b[0m");
+        assert_eq!(truncate("\x1b[92mhello\x1b[0m", 3), "\x1b[92mhe…\x1b[0m");
+        assert_eq!(
+            truncate("hi \x1b[92mthere\x1b[0m", 4),
+            "hi \x1b[92m…\x1b[0m"
+        );
+
+        // Longer markers take more room, or get cut.
+        assert_eq!(Table::truncate("hello", 4, "..."), "h...");
+        assert_eq!(Table::truncate("hello", 2, "..."), "..");
+    }
+
     #[test]
     fn strip_ansi_colors() {
         let strip = Table::strip_ansi_colors;
@@ -1200,8 +8014,12 @@ SHORT  WITH SPACE  LAST COLUMN
 
         // Malformed ANSI sequences.
         assert_eq!(strip("\x1b0;92mhello\x1b0m"), "\x1b0;92mhello\x1b0m");
-        assert_eq!(strip("\x1b[31hello"), ""); // missing 'm'
-        assert_eq!(strip("text with \x1b[no escape\x1b[0m"), "text with ");
+        assert_eq!(strip("\x1b[31;"), ""); // missing final byte
+        assert_eq!(strip("\x1b[31hello"), "ello"); // `h` is a final byte
+        assert_eq!(
+            strip("text with \x1b[no escape\x1b[0m"),
+            "text with o escape"
+        );
         assert_eq!(strip("\x1b[31mHello"), "Hello");
         assert_eq!(strip("text\x1b"), "text\x1b");
         assert_eq!(strip("text\x1b["), "text");
@@ -1211,5 +8029,13 @@ SHORT  WITH SPACE  LAST COLUMN
         assert_eq!(strip("\x1b[31m\x1b[32mtext\x1b[0m"), "text");
 
         assert_eq!(strip("\x1b[0;90mfoo\x1b[0m").len(), 3);
+
+        // Other CSI sequences.
+        assert_eq!(strip("\x1b[2Kprogress\x1b[K"), "progress");
+        assert_eq!(strip("\x1b[1;1Htop left\x1b[H"), "top left");
+        assert_eq!(strip("\x1b[?25lhidden cursor\x1b[?25h"), "hidden cursor");
+        assert_eq!(strip("\x1b[K"), "");
+        assert_eq!(Table::visible_width("\x1b[2K\x1b[1Gab"), 2);
+        assert!(matches!(strip("no sequence"), Cow::Borrowed(_)));
     }
 }